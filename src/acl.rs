@@ -0,0 +1,278 @@
+//! Permissions of actors on the edges of the state machine.
+//!
+//! Sessions driven by multiple parties attach the identity of the acting party, an
+//! [`Actor`], to each dispatched command, see [`AnyMachine::dispatch`]. The [`Acl`]
+//! service is installed as layer of the middleware, so it's consulted before every edge
+//! and records every attempt, whether it was granted, denied or didn't describe an edge
+//! at all.
+//!
+//! Edges without rules are open to every actor. Once an edge is restricted, only the
+//! actors allowed onto it may apply it. Edges applied without any actor, eg by drivers
+//! of the machine, are only granted when open.
+//!
+//! ```
+//! use automaton_test::acl::{Acl, Actor};
+//! use automaton_test::dynamic::AnyMachine;
+//! use automaton_test::function::error::ErrorKind;
+//! use automaton_test::registry::{Edge, StateId};
+//!
+//! let acl = Acl::default();
+//! acl.restrict(Edge::INPUT_TO_FINISHED, &[Actor::from("host")]);
+//!
+//! let mut machine: AnyMachine = AnyMachine::default();
+//! machine.middleware_mut().push(acl.clone());
+//! machine.dispatch("guest: transition input".parse().unwrap()).unwrap();
+//! let error = machine
+//!     .dispatch("guest: finish".parse().unwrap())
+//!     .unwrap_err();
+//! assert_eq!(error.kind(), ErrorKind::PermissionError);
+//! assert_eq!(machine.state(), StateId::WaitInput);
+//! // Edges applied without actor are checked as well.
+//! assert!(machine.execute("finish".parse().unwrap()).is_err());
+//!
+//! assert!(machine.dispatch("guest: pullup".parse().unwrap()).is_err());
+//! machine.dispatch("host: finish".parse().unwrap()).unwrap();
+//! let attempts: Vec<String> = acl.attempts().iter().map(|a| a.to_string()).collect();
+//! assert_eq!(attempts[1], "guest: Wait<Input> -> Finished | denied");
+//! assert_eq!(attempts[2], "(anonymous): Wait<Input> -> Finished | denied");
+//! assert_eq!(attempts[3], "guest: pullup | unresolved");
+//! assert_eq!(attempts[4], "host: Wait<Input> -> Finished | granted");
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use self::error::PermissionDeniedError;
+use command::Command;
+use context::Context;
+use dynamic::AnyMachine;
+use function::error::{ErrorKind, MachineError, RuntimeConstraintError};
+use marker::Service;
+use middleware::{Middleware, Step};
+use registry::Edge;
+
+pub mod error {
+    //! Types for simplifying error handling syntax.
+
+    use super::Actor;
+    use registry::Edge;
+
+    /// Error thrown when an edge is applied by a party not allowed onto it.
+    #[derive(Debug, Fail)]
+    pub enum PermissionDeniedError {
+        /// The actor is not allowed onto the edge.
+        #[fail(display = "Actor {} is not permitted to apply {}", _0, _1)]
+        Actor(Actor, Edge),
+        /// The edge is restricted, but was applied without any actor.
+        #[fail(display = "Applying {} requires a permitted actor", _0)]
+        Anonymous(Edge),
+    }
+}
+
+/// Identity of a party acting upon the state machine.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Actor(pub String);
+
+impl<'a> From<&'a str> for Actor {
+    fn from(name: &'a str) -> Self {
+        Actor(name.to_string())
+    }
+}
+
+impl Display for Actor {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A command together with the actor who dispatched it.
+///
+/// Written as `<actor>: <command>`, eg `host: finish`.
+#[derive(Debug, Clone)]
+pub struct Dispatch {
+    /// The acting party.
+    pub actor: Actor,
+    /// The requested move.
+    pub command: Command,
+}
+
+impl Display for Dispatch {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.actor, self.command)
+    }
+}
+
+impl FromStr for Dispatch {
+    type Err = RuntimeConstraintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s.find(':');
+        match split.map(|i| (s[..i].trim(), &s[i + 1..])) {
+            Some((actor, command)) if !actor.is_empty() => Ok(Dispatch {
+                actor: Actor::from(actor),
+                command: command.parse()?,
+            }),
+            _ => Err(("<actor>: <command>", s).into()),
+        }
+    }
+}
+
+/// The move an actor attempted.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Target {
+    /// An edge starting from the current state.
+    Edge(Edge),
+    /// A command which doesn't describe any edge from the current state, as written.
+    Unresolved(String),
+}
+
+/// Record of one actor attempting to apply an edge.
+///
+/// Written as `<actor>: <edge> | granted`, `<actor>: <edge> | denied` or
+/// `<actor>: <command> | unresolved`. Attempts without actor are written as
+/// `(anonymous)`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Attempt {
+    /// The acting party, if any.
+    pub actor: Option<Actor>,
+    /// The move the actor attempted.
+    pub target: Target,
+    /// Whether the actor was allowed to apply the edge, unresolved commands never are.
+    pub granted: bool,
+}
+
+impl Display for Attempt {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.actor {
+            Some(ref actor) => write!(f, "{}: ", actor)?,
+            None => write!(f, "(anonymous): ")?,
+        }
+        match self.target {
+            Target::Edge(edge) if self.granted => write!(f, "{} | granted", edge),
+            Target::Edge(edge) => write!(f, "{} | denied", edge),
+            Target::Unresolved(ref command) => write!(f, "{} | unresolved", command),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Permissions {
+    /// Actors allowed onto each restricted edge.
+    rules: HashMap<Edge, HashSet<Actor>>,
+    /// Every attempt, in the order they were made.
+    attempts: Vec<Attempt>,
+}
+
+/// Service holding the permissions of actors and the attempts made, consulted as layer
+/// of the middleware, see [`Chain::push`](::middleware::Chain::push).
+///
+/// Clones share the permissions and attempts, so one clone can be installed on the
+/// state machine while another is used to manage permissions.
+#[derive(Debug, Clone, Default)]
+pub struct Acl {
+    permissions: Arc<Mutex<Permissions>>,
+}
+
+impl Service for Acl {}
+
+impl Acl {
+    fn lock<'a>(&'a self) -> MutexGuard<'a, Permissions> {
+        self.permissions.lock().expect("Acl lock poisoned")
+    }
+
+    /// Only allow the provided actors, and those allowed before, onto the edge.
+    pub fn restrict(&self, edge: Edge, actors: &[Actor]) {
+        self.lock()
+            .rules
+            .entry(edge)
+            .or_default()
+            .extend(actors.iter().cloned());
+    }
+
+    /// Open the edge to every actor again, returning the actors which were allowed.
+    pub fn open(&self, edge: Edge) -> Option<HashSet<Actor>> {
+        self.lock().rules.remove(&edge)
+    }
+
+    /// Whether the actor, or a move without actor, may apply the edge.
+    pub fn permits(&self, actor: Option<&Actor>, edge: Edge) -> bool {
+        match (self.lock().rules.get(&edge), actor) {
+            (Some(actors), Some(actor)) => actors.contains(actor),
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+
+    /// Every attempt, in the order they were made.
+    pub fn attempts(&self) -> Vec<Attempt> {
+        self.lock().attempts.clone()
+    }
+
+    /// Verify the actor may apply the edge onto the machine, and record the attempt.
+    ///
+    /// # Errors
+    /// Unauthorised attempts are rejected with [`ErrorKind::PermissionError`].
+    pub fn check<C>(
+        &self,
+        machine: &AnyMachine<C>,
+        actor: Option<&Actor>,
+        edge: Edge,
+    ) -> Result<(), MachineError>
+    where
+        C: Context,
+    {
+        let granted = self.permits(actor, edge);
+        self.lock().attempts.push(Attempt {
+            actor: actor.cloned(),
+            target: Target::Edge(edge),
+            granted,
+        });
+        if granted {
+            return Ok(());
+        }
+        let error = match actor {
+            Some(actor) => PermissionDeniedError::Actor(actor.clone(), edge),
+            None => PermissionDeniedError::Anonymous(edge),
+        };
+        Err(machine.error(ErrorKind::PermissionError, error))
+    }
+}
+
+impl<C> Middleware<C> for Acl
+where
+    C: Context,
+{
+    fn before(&mut self, machine: &AnyMachine<C>, step: &Step) -> Result<(), MachineError> {
+        self.check(machine, step.actor.as_ref(), step.edge)
+    }
+
+    fn unresolved(
+        &mut self,
+        _: &AnyMachine<C>,
+        command: &Command,
+        actor: Option<&Actor>,
+        _: &MachineError,
+    ) {
+        self.lock().attempts.push(Attempt {
+            actor: actor.cloned(),
+            target: Target::Unresolved(command.to_string()),
+            granted: false,
+        });
+    }
+}
+
+impl<C> AnyMachine<C>
+where
+    C: Context,
+{
+    /// Apply the dispatched command onto the machine on behalf of its actor, see
+    /// [`AnyMachine::execute`].
+    ///
+    /// The actor is provided to each layer of the middleware, see
+    /// [`Step::actor`](::middleware::Step::actor). The applied edge is returned.
+    pub fn dispatch(&mut self, dispatch: Dispatch) -> Result<Edge, MachineError> {
+        self.execute_as(dispatch.command, Some(dispatch.actor))
+    }
+}
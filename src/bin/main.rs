@@ -1,37 +1,77 @@
 extern crate automaton_test;
+extern crate failure;
 
 use std::env;
-use std::marker::PhantomData;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process;
 
-use automaton_test::service::StackStorage;
+use automaton_test::command::{usage, Command};
+use automaton_test::dynamic::AnyMachine;
+use automaton_test::function::error::MachineError;
+use automaton_test::scenario::Scenario;
 use automaton_test::state::*;
 use automaton_test::stm::*;
+use automaton_test::trace::{check_golden, GoldenMode, TraceRecorder};
 use automaton_test::transaction::{Epsilon, PrintTransaction};
 use automaton_test::*;
+use failure::Fail;
 
 fn new_machine() -> Machine<Wait<Start>> {
-    Machine {
-        state: PhantomData,
-        transaction: Epsilon,
-        storage: StackStorage { tape: vec![] },
-    }
+    Machine::new()
 }
 
 fn main() {
     // DBG; This will enable Failure to print out full backtraces.
     // env::set_var("RUST_BACKTRACE", "1");
 
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("--repl") => repl(),
+        Some("--scenario") => match args.get(1) {
+            Some(path) => scenario(path),
+            None => {
+                eprintln!("Usage: main --scenario <file>");
+                process::exit(2);
+            }
+        },
+        Some("--golden") => match args.get(1) {
+            Some(path) => {
+                let mode = match args.get(2).map(String::as_str) {
+                    Some("--regenerate") => GoldenMode::Regenerate,
+                    _ => GoldenMode::from_env(),
+                };
+                golden(path, mode)
+            }
+            None => {
+                eprintln!("Usage: main --golden <file> [--regenerate]");
+                process::exit(2);
+            }
+        },
+        _ => example(),
+    }
+}
+
+/// Runs one hard-coded path through the state machine.
+fn example() {
     let start_state = new_machine();
 
     // DBG; The following syntax can/will be made simpler by implementing the TransitionInto-
     // counterpart of TransitionFrom.
-    let input_state: Machine<Wait<Input>> = start_state.transition(Epsilon);
+    let input_state: Machine<Wait<Input>> =
+        start_state.transition(Epsilon).expect("Transition Error");
 
-    let action_state: Machine<Action<Print>> = input_state.pushdown(PrintTransaction("Hello"));
+    // The text is built at runtime, the Transaction owns it.
+    let greeting = ["Hello", "World"].join(" ");
+    let action_state: Machine<Action<Print>> = input_state
+        .pushdown(PrintTransaction(greeting))
+        .expect("Transition Error");
 
     println!("Printing transaction: {:?}", action_state.transaction);
 
-    let deep_action_state: Machine<Action<Load>> = action_state.pushdown(Epsilon);
+    let deep_action_state: Machine<Action<Load>> =
+        action_state.pushdown(Epsilon).expect("Transition Error");
 
     let action_state: Machine<Action<Print>> =
         deep_action_state.pullup().expect("Transition Error");
@@ -40,7 +80,140 @@ fn main() {
 
     let input_state: Machine<Wait<Input>> = action_state.pullup().expect("Transition Error");
 
-    let finished_state: Machine<Finished> = input_state.transition(Epsilon);
+    let finished_state: Machine<Finished> =
+        input_state.transition(Epsilon).expect("Transition Error");
 
     println!("{:?}", finished_state);
 }
+
+/// Interactively drives the state machine with commands read from stdin.
+fn repl() {
+    let mut machine = AnyMachine::default();
+    let stdin = io::stdin();
+
+    println!("Type `help` for the list of commands.");
+    show(&machine);
+    loop {
+        print!("> ");
+        io::stdout().flush().expect("Flushing stdout failed");
+
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => {
+                println!();
+                break;
+            }
+            Ok(_) => {}
+        }
+
+        match line.trim() {
+            "" => continue,
+            "quit" | "exit" => break,
+            "help" => help(),
+            "show" => show(&machine),
+            "journal" => print!("{}", machine.journal()),
+            line => match line.parse::<Command>() {
+                Ok(command) => match machine.execute(command) {
+                    Ok(edge) => {
+                        println!("Applied `{}`", edge);
+                        show(&machine);
+                    }
+                    Err(e) => report(&e),
+                },
+                Err(e) => println!("Invalid command: {}", e),
+            },
+        }
+    }
+}
+
+fn help() {
+    println!("Commands:");
+    println!("  transition <state> [transaction]  Transition into the state");
+    println!("  push <state> [transaction]        Pushdown into the state");
+    println!("  pullup [state]                    Pullup into the previous state");
+    println!("  finish                            Transition into `Finished`");
+    println!("  show                              Show the current state");
+    println!("  journal                           Show all applied transitions");
+    println!("  quit                              Leave the interactive mode");
+    println!("States are written in full, `Action<Print>`, or by inner name, `print`.");
+    println!("Transactions are written as `Epsilon` or `Print \"<text>\"`, or omitted.");
+}
+
+fn show(machine: &AnyMachine) {
+    println!("State:       {}", machine.state());
+    println!("Transaction: {}", machine.transaction());
+    let tape: Vec<String> = machine
+        .storage()
+        .tape
+        .iter()
+        .map(|t| t.to_string())
+        .collect();
+    println!("Stack:       [{}]", tape.join(", "));
+    let moves = machine.moves();
+    if moves.is_empty() {
+        println!("No legal moves left.");
+    } else {
+        println!("Legal moves:");
+        for edge in moves {
+            println!("  {:<36}{}", usage(edge), edge);
+        }
+    }
+}
+
+fn report(e: &MachineError) {
+    println!("Error: {}", e);
+    let mut cause = e.cause();
+    while let Some(c) = cause {
+        println!("Caused by: {}", c);
+        cause = c.cause();
+    }
+    println!("Snapshot: {:?}", e.snapshot());
+}
+
+fn load_scenario(path: &str) -> Scenario {
+    let text = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Reading scenario `{}` failed: {}", path, e);
+        process::exit(2);
+    });
+    text.parse().unwrap_or_else(|e| {
+        eprintln!("Parsing scenario `{}` failed: {}", path, e);
+        process::exit(2);
+    })
+}
+
+/// Executes the scenario file at the provided path and exits non-zero on divergence.
+fn scenario(path: &str) {
+    let scenario = load_scenario(path);
+    match scenario.run() {
+        Ok(machine) => {
+            println!(
+                "Scenario `{}` passed: {} steps, ended in `{}`",
+                path,
+                scenario.steps.len(),
+                machine.state()
+            );
+        }
+        Err(e) => {
+            eprintln!("Scenario `{}` failed", path);
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Compares the trace of the scenario file at the provided path against its golden file,
+/// which has the same path with the `trace` extension.
+fn golden(path: &str, mode: GoldenMode) {
+    let trace = TraceRecorder::record(&load_scenario(path));
+    let golden_path = Path::new(path).with_extension("trace");
+    match check_golden(&golden_path, &trace, mode) {
+        Ok(()) if mode == GoldenMode::Regenerate => {
+            println!("Regenerated `{}`", golden_path.display());
+        }
+        Ok(()) => println!("Trace matches `{}`", golden_path.display()),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Cancellation of [`Action`](::state::Action) states in progress.
+//!
+//! A [`CancellationToken`] can be shared with other threads, which request cancellation
+//! by triggering it. The thread driving the state machine checks the token between steps
+//! and unwinds the active actions, through their pullup transitions, up to the nearest
+//! [`Wait`](::state::Wait) state.
+//!
+//! ```
+//! use std::thread;
+//!
+//! use automaton_test::cancel::CancellationToken;
+//! use automaton_test::dynamic::AnyMachine;
+//! use automaton_test::registry::StateId;
+//!
+//! let token = CancellationToken::default();
+//! let mut machine: AnyMachine = AnyMachine::default();
+//! machine.execute("transition input".parse().unwrap()).unwrap();
+//! machine.execute("push print \"Hello\"".parse().unwrap()).unwrap();
+//! machine.execute("push load".parse().unwrap()).unwrap();
+//!
+//! let remote = token.clone();
+//! thread::spawn(move || remote.cancel()).join().unwrap();
+//!
+//! let cancellation = token.check(&mut machine).unwrap().expect("Cancelled");
+//! assert_eq!(cancellation.from, StateId::ActionLoad);
+//! assert_eq!(cancellation.edges.len(), 2);
+//! assert_eq!(machine.state(), StateId::WaitInput);
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use command::Command;
+use context::Context;
+use dynamic::AnyMachine;
+use function::error::MachineError;
+use marker::Service;
+use registry::{Edge, StateId};
+
+/// Service for requesting cancellation of the active actions, from any thread.
+///
+/// Clones share the same request.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Service for CancellationToken {}
+
+/// Report of cancelled actions.
+///
+/// Cancellation is not a failure of the state machine, so it's reported separately
+/// from [`MachineError`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Cancellation {
+    /// The state the machine resided in when cancellation was handled.
+    pub from: StateId,
+    /// The pullup edges applied to reach the nearest Wait state, in order.
+    ///
+    /// Empty when no action was in progress.
+    pub edges: Vec<Edge>,
+}
+
+impl CancellationToken {
+    /// Request cancellation of the active actions.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true if cancellation was requested and not handled yet.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Handle a pending cancellation request by unwinding the machine, see
+    /// [`AnyMachine::unwind`].
+    ///
+    /// The request is consumed, so the token can be reused afterwards.
+    ///
+    /// # Errors
+    /// A failing pullup is returned, the machine is left in the state it was unwound to.
+    pub fn check<C>(
+        &self,
+        machine: &mut AnyMachine<C>,
+    ) -> Result<Option<Cancellation>, MachineError>
+    where
+        C: Context,
+    {
+        if !self.cancelled.swap(false, Ordering::SeqCst) {
+            return Ok(None);
+        }
+
+        let from = machine.state();
+        let edges = machine.unwind()?;
+        Ok(Some(Cancellation { from, edges }))
+    }
+}
+
+impl<C> AnyMachine<C>
+where
+    C: Context,
+{
+    /// Pullup until the machine resides in a state which is not an
+    /// [`Action`](::state::Action) state.
+    ///
+    /// The applied edges are returned in order.
+    pub fn unwind(&mut self) -> Result<Vec<Edge>, MachineError> {
+        let mut edges = vec![];
+        while self.state().is_actionable() {
+            edges.push(self.execute(Command::Pullup(None))?);
+        }
+        Ok(edges)
+    }
+}
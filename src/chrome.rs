@@ -0,0 +1,235 @@
+//! Export of state machine runs as Chrome trace-event JSON.
+//!
+//! The resulting document opens in `chrome://tracing` and in Perfetto. Each state is a
+//! span lasting from the moment it's entered until it's left. A pushdown opens a span
+//! within the span of the state it was pushed from, the matching pullup closes it, so
+//! nested actions show up as nested spans. The Transaction of each state is attached
+//! as argument of its span. Failed commands show up as instant events.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use automaton_test::chrome::ChromeTrace;
+//! use automaton_test::dynamic::AnyMachine;
+//! use automaton_test::service::ManualClock;
+//!
+//! let clock = ManualClock::default();
+//! let mut machine: AnyMachine = AnyMachine::default();
+//! let mut trace = ChromeTrace::new(clock.clone(), &machine);
+//! for command in &["transition input", r#"push print "Hello""#, "push load", "pullup", "pullup"] {
+//!     clock.advance(Duration::from_millis(5));
+//!     trace.execute(&mut machine, command.parse().unwrap()).unwrap();
+//! }
+//! clock.advance(Duration::from_millis(5));
+//! let json = trace.finish();
+//! assert!(json.contains(r#""name":"Action<Print>","cat":"pushdown","ph":"B","ts":10000"#));
+//! assert!(json.contains(r#""args":{"transaction":"Print \"Hello\""}"#));
+//! ```
+
+use std::time::Duration;
+
+use serde_json;
+
+use command::Command;
+use context::Context;
+use dynamic::AnyMachine;
+use function::error::MachineError;
+use registry::{Edge, EdgeKind};
+use service::Clock;
+
+/// Identifier of the process within the trace.
+const PID: u32 = 1;
+/// Identifier of the thread within the trace, all spans nest on the same thread.
+const TID: u32 = 1;
+
+/// Arguments attached to an event.
+#[derive(Debug, Clone, Default, Serialize)]
+struct Args {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transaction: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// One event in the trace-event format.
+#[derive(Debug, Clone, Serialize)]
+struct TraceEvent {
+    name: String,
+    cat: &'static str,
+    /// Phase of the event, `B` begins a span, `E` ends it and `i` is instant.
+    ph: &'static str,
+    /// Timestamp in microseconds.
+    ts: u64,
+    pid: u32,
+    tid: u32,
+    /// Scope of instant events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    s: Option<&'static str>,
+    #[serde(skip_serializing_if = "Args::is_empty")]
+    args: Args,
+}
+
+impl Args {
+    fn is_empty(&self) -> bool {
+        self.transaction.is_none() && self.error.is_none()
+    }
+}
+
+/// Top level structure of the trace-event JSON document.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Document<'a> {
+    trace_events: &'a [TraceEvent],
+    display_time_unit: &'static str,
+}
+
+/// Recorder of state machine runs in the trace-event format.
+#[derive(Debug, Clone)]
+pub struct ChromeTrace<K>
+where
+    K: Clock,
+{
+    clock: K,
+    /// Time the recording started, timestamps are relative to it.
+    origin: Duration,
+    events: Vec<TraceEvent>,
+    /// Name and category of the spans which are open, the innermost last.
+    open: Vec<(String, &'static str)>,
+}
+
+impl<K> ChromeTrace<K>
+where
+    K: Clock,
+{
+    /// Start recording, the span of the current state of the machine opens now.
+    pub fn new<C>(clock: K, machine: &AnyMachine<C>) -> Self
+    where
+        C: Context,
+    {
+        let origin = clock.now();
+        let mut trace = ChromeTrace {
+            clock,
+            origin,
+            events: vec![],
+            open: vec![],
+        };
+        trace.begin(machine, "initial");
+        trace
+    }
+
+    /// Execute the command onto the machine, see [`AnyMachine::execute`], and record
+    /// the spans it opens and closes.
+    pub fn execute<C>(
+        &mut self,
+        machine: &mut AnyMachine<C>,
+        command: Command,
+    ) -> Result<Edge, MachineError>
+    where
+        C: Context,
+    {
+        let result = machine.execute(command.clone());
+        match result {
+            Ok(edge) => self.record(edge, machine),
+            Err(ref e) => {
+                let event = TraceEvent {
+                    name: command.to_string(),
+                    cat: "error",
+                    ph: "i",
+                    ts: self.timestamp(),
+                    pid: PID,
+                    tid: TID,
+                    s: Some("t"),
+                    args: Args {
+                        error: Some(format!("{:?}: {}", e.kind(), e)),
+                        ..Args::default()
+                    },
+                };
+                self.events.push(event);
+            }
+        }
+        result
+    }
+
+    /// Record the spans opened and closed by an edge which was applied onto the machine
+    /// in some other way.
+    ///
+    /// The provided machine resides in the state the edge leads into.
+    pub fn record<C>(&mut self, edge: Edge, machine: &AnyMachine<C>)
+    where
+        C: Context,
+    {
+        match edge.kind {
+            EdgeKind::Transition => {
+                self.end();
+                self.begin(machine, "transition");
+            }
+            EdgeKind::Pushdown => self.begin(machine, "pushdown"),
+            EdgeKind::Pullup => self.end(),
+        }
+    }
+
+    /// Close every open span and write the trace-event JSON document.
+    pub fn finish(mut self) -> String {
+        while !self.open.is_empty() {
+            self.end();
+        }
+        let document = Document {
+            trace_events: &self.events,
+            display_time_unit: "ms",
+        };
+        serde_json::to_string(&document).expect("Serializing plain data never fails")
+    }
+
+    /// Microseconds since the recording started, a clock moving backwards reads as the
+    /// start.
+    fn timestamp(&self) -> u64 {
+        let elapsed = self
+            .clock
+            .now()
+            .checked_sub(self.origin)
+            .unwrap_or_default();
+        elapsed.as_secs() * 1_000_000 + u64::from(elapsed.subsec_micros())
+    }
+
+    /// Open the span of the current state of the machine.
+    fn begin<C>(&mut self, machine: &AnyMachine<C>, cat: &'static str)
+    where
+        C: Context,
+    {
+        let name = machine.state().to_string();
+        let event = TraceEvent {
+            name: name.clone(),
+            cat,
+            ph: "B",
+            ts: self.timestamp(),
+            pid: PID,
+            tid: TID,
+            s: None,
+            args: Args {
+                transaction: Some(machine.transaction().to_string()),
+                ..Args::default()
+            },
+        };
+        self.events.push(event);
+        self.open.push((name, cat));
+    }
+
+    /// Close the innermost open span.
+    fn end(&mut self) {
+        let (name, cat) = match self.open.pop() {
+            Some(span) => span,
+            None => return,
+        };
+        let event = TraceEvent {
+            name,
+            cat,
+            ph: "E",
+            ts: self.timestamp(),
+            pid: PID,
+            tid: TID,
+            s: None,
+            args: Args::default(),
+        };
+        self.events.push(event);
+    }
+}
@@ -0,0 +1,582 @@
+//! Generation of typestate code from a machine specification.
+//!
+//! The specification is a [`Definition`], the same document a [`DynMachine`] runs. From
+//! it the generator writes the state structs, a `TransactionItem` with conversions for
+//! each Transaction, a `Machine` with its transition implementations, a `StateId` and
+//! the edge registry `TRANSITIONS`, which [`Analysis`] can check. Generating both from
+//! one file keeps the typestate code and the flow description from drifting apart.
+//!
+//! The generator is intended to be called from a build script:
+//!
+//! ```ignore
+//! // build.rs
+//! extern crate automaton_test;
+//!
+//! fn main() {
+//!     automaton_test::codegen::build("flow.toml", "flow.rs").unwrap();
+//! }
+//!
+//! // src/lib.rs
+//! extern crate automaton_test;
+//!
+//! pub mod flow {
+//!     include!(concat!(env!("OUT_DIR"), "/flow.rs"));
+//! }
+//! ```
+//!
+//! ```
+//! use automaton_test::codegen::generate;
+//! use automaton_test::runtime::Definition;
+//!
+//! let definition = Definition::from_json(r#"{
+//!     "initial": "Start",
+//!     "states": [
+//!         { "name": "Start", "group": "wait" },
+//!         { "name": "Print", "group": "action", "fields": ["text"] }
+//!     ],
+//!     "edges": [
+//!         { "kind": "pushdown", "from": "Start", "to": "Print" },
+//!         { "kind": "pullup", "from": "Print", "to": "Start" }
+//!     ]
+//! }"#).unwrap();
+//!
+//! let code = generate(&definition).unwrap();
+//! assert!(code.contains("pub struct PrintTransaction {"));
+//! assert!(code.contains("impl PushdownFrom<Machine<Wait<Start>>, TransactionItem> for Machine<Action<Print>>"));
+//!
+//! // Keywords are no valid field names, nor are names which are already declared.
+//! let mut definition = definition;
+//! definition.states[1].fields.push("type".to_string());
+//! assert!(generate(&definition).is_err());
+//! definition.states[1].fields[1] = "text".to_string();
+//! assert!(generate(&definition).is_err());
+//! ```
+//!
+//! [`Analysis`]: ::registry::analysis::Analysis
+//! [`DynMachine`]: ::runtime::DynMachine
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use self::error::CodegenError;
+use function::error::RuntimeConstraintError;
+use registry::EdgeKind;
+use runtime::{Definition, Group, StateDefinition};
+
+pub mod error {
+    //! Types for simplifying error handling syntax.
+
+    use std::io;
+
+    use function::error::RuntimeConstraintError;
+    use runtime::error::DefinitionError;
+
+    /// Error thrown when code can not be generated from a specification.
+    #[derive(Debug, Fail)]
+    pub enum CodegenError {
+        /// The specification could not be read or the code could not be written.
+        #[fail(display = "Accessing `{}` failed: {}", path, inner)]
+        Io {
+            /// Path of the accessed file.
+            path: String,
+            /// Reason of the failure.
+            #[cause]
+            inner: io::Error,
+        },
+        /// The specification could not be loaded.
+        #[fail(display = "{}", _0)]
+        Definition(#[cause] DefinitionError),
+        /// The specification can not be expressed as Rust code.
+        #[fail(display = "Unsupported specification: {}", _0)]
+        Unsupported(#[cause] RuntimeConstraintError),
+        /// The output directory is unknown, Cargo only sets it for build scripts.
+        #[fail(display = "Environment variable `OUT_DIR` is not set")]
+        OutDir,
+    }
+}
+
+/// Names of the generated and imported items, which can not be used for states.
+///
+/// MUST stay in sync with [`write_header`] and the items written by [`generate`] !
+const RESERVED: &[&str] = &[
+    // Generated items.
+    "Machine",
+    "StateId",
+    "TransactionItem",
+    "TRANSITIONS",
+    // Imported from `std`, including the prelude names used by the generated code.
+    "Clone",
+    "Debug",
+    "Default",
+    "Err",
+    "From",
+    "Ok",
+    "PhantomData",
+    "Result",
+    "Self",
+    "String",
+    "TryFrom",
+    // Imported from `automaton_test`.
+    "Action",
+    "ActionableMarker",
+    "Checked",
+    "Edge",
+    "EdgeKind",
+    "Epsilon",
+    "ErrorKind",
+    "MachineError",
+    "NonTerminalState",
+    "PullupFrom",
+    "PushdownFrom",
+    "RuntimeConstraintError",
+    "Service",
+    "ServiceCompliance",
+    "SnapshottedErrorExt",
+    "StackStorage",
+    "State",
+    "StateContainer",
+    "StateSet",
+    "TopLevelMarker",
+    "Transaction",
+    "TransactionContainer",
+    "TransitionFrom",
+    "Validate",
+    "Wait",
+    "WaitableMarker",
+    "pack_transaction",
+    "unpack_transaction",
+];
+
+/// Keywords of every Rust edition, which are not usable as identifiers.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Load the specification, generate the code and write it into `OUT_DIR`.
+///
+/// The specification is parsed as JSON when its extension is `json`, as TOML otherwise.
+/// Cargo is instructed to rerun the build script when the specification changes.
+///
+/// # Errors
+/// Calling this function outside of a build script fails, since `OUT_DIR` is not set.
+///
+/// ```
+/// use automaton_test::codegen::build;
+/// use automaton_test::codegen::error::CodegenError;
+///
+/// match build("flow.toml", "flow.rs") {
+///     Err(CodegenError::OutDir) => {}
+///     other => panic!("Unexpected result {:?}", other),
+/// }
+/// ```
+pub fn build<P: AsRef<Path>>(spec: P, out_file: &str) -> Result<(), CodegenError> {
+    let spec = spec.as_ref();
+    let out_dir = env::var_os("OUT_DIR").ok_or(CodegenError::OutDir)?;
+    let out_path = PathBuf::from(out_dir).join(out_file);
+    let io_error = |path: &Path| {
+        let path = path.display().to_string();
+        move |inner| CodegenError::Io { path, inner }
+    };
+
+    println!("cargo:rerun-if-changed={}", spec.display());
+    let document = fs::read_to_string(spec).map_err(io_error(spec))?;
+    let definition = match spec.extension().and_then(|e| e.to_str()) {
+        Some("json") => Definition::from_json(&document),
+        _ => Definition::from_toml(&document),
+    }
+    .map_err(CodegenError::Definition)?;
+
+    let code = generate(&definition).map_err(CodegenError::Unsupported)?;
+    fs::write(&out_path, code).map_err(io_error(&out_path))
+}
+
+/// Generate the typestate code for the provided specification.
+///
+/// # Errors
+/// State and field names must be valid Rust identifiers, not clashing with the
+/// generated items. Each field is declared once per state. Terminal states can not be
+/// part of a group.
+pub fn generate(definition: &Definition) -> Result<String, RuntimeConstraintError> {
+    for state in &definition.states {
+        if !is_identifier(&state.name) || RESERVED.contains(&state.name.as_str()) {
+            return Err(("a state name usable as Rust type", &state.name).into());
+        }
+        // `Wait` and `Action` are never terminal.
+        if state.terminal && state.group.is_some() {
+            return Err(("terminal states outside of groups", &state.name).into());
+        }
+        if let Some(field) = state.fields.iter().find(|f| !is_identifier(f)) {
+            return Err(("a field name usable as Rust identifier", field).into());
+        }
+        let mut fields = BTreeSet::new();
+        if let Some(field) = state.fields.iter().find(|f| !fields.insert(f.as_str())) {
+            return Err(("unique field names", field).into());
+        }
+        // The Transaction of a state with fields is generated as `<name>Transaction`.
+        let transaction = transaction_of(state);
+        if !state.fields.is_empty()
+            && (RESERVED.contains(&transaction.as_str())
+                || definition.state(&transaction).is_some())
+        {
+            return Err(("a unique Transaction name", transaction).into());
+        }
+    }
+
+    // Writing into a String never fails.
+    let mut code = String::new();
+    write_header(&mut code);
+    for state in &definition.states {
+        write_state(&mut code, state);
+    }
+    write_transaction_item(&mut code, definition);
+    write_machine(&mut code, definition);
+    for edge in &definition.edges {
+        let from = type_of(definition.state(&edge.from).expect("Validated definition"));
+        let to = type_of(definition.state(&edge.to).expect("Validated definition"));
+        write_edge(&mut code, edge.kind, &from, &to);
+    }
+    write_registry(&mut code, definition);
+    Ok(code)
+}
+
+/// Returns true if the provided name is a valid, non-raw, Rust identifier.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    name != "_" && !KEYWORDS.contains(&name) && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The type of the state, as used within `Machine<X>`.
+fn type_of(state: &StateDefinition) -> String {
+    match state.group {
+        Some(Group::Wait) => format!("Wait<{}>", state.name),
+        Some(Group::Action) => format!("Action<{}>", state.name),
+        None => state.name.clone(),
+    }
+}
+
+/// The Transaction type of the state.
+fn transaction_of(state: &StateDefinition) -> String {
+    if state.fields.is_empty() {
+        "Epsilon".to_string()
+    } else {
+        format!("{}Transaction", state.name)
+    }
+}
+
+fn write_header(code: &mut String) {
+    let _ = writeln!(
+        code,
+        "// Generated by `automaton_test::codegen`, DO NOT EDIT!
+
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+
+#[allow(unused_imports)]
+use ::automaton_test::function::error::{{ErrorKind, MachineError, RuntimeConstraintError, SnapshottedErrorExt}};
+#[allow(unused_imports)]
+use ::automaton_test::function::helper::{{pack_transaction, unpack_transaction}};
+#[allow(unused_imports)]
+use ::automaton_test::function::{{ServiceCompliance, State, StateContainer, Validate}};
+#[allow(unused_imports)]
+use ::automaton_test::marker::*;
+#[allow(unused_imports)]
+use ::automaton_test::registry::{{Edge, EdgeKind, StateSet}};
+#[allow(unused_imports)]
+use ::automaton_test::service::StackStorage;
+#[allow(unused_imports)]
+use ::automaton_test::state::{{Action, Wait}};
+#[allow(unused_imports)]
+use ::automaton_test::stm::{{Checked, PullupFrom, PushdownFrom, TransitionFrom}};
+#[allow(unused_imports)]
+use ::automaton_test::transaction::Epsilon;
+"
+    );
+}
+
+fn write_state(code: &mut String, state: &StateDefinition) {
+    let name = &state.name;
+    let transaction = transaction_of(state);
+    if !state.fields.is_empty() {
+        let _ = writeln!(code, "/// Transaction to be received by `{}`.", name);
+        let _ = writeln!(code, "#[derive(Debug, Clone)]");
+        let _ = writeln!(code, "pub struct {} {{", transaction);
+        for field in &state.fields {
+            let _ = writeln!(code, "    #[allow(missing_docs)]");
+            let _ = writeln!(code, "    pub {}: String,", field);
+        }
+        let _ = writeln!(code, "}}");
+        let _ = writeln!(code, "impl Transaction for {} {{}}", transaction);
+        let _ = writeln!(code, "impl Validate for {} {{}}\n", transaction);
+    }
+
+    let _ = writeln!(code, "/// State `{}`.", name);
+    let _ = writeln!(code, "#[derive(Debug, Clone)]");
+    let _ = writeln!(code, "pub struct {}();", name);
+    let _ = writeln!(code, "impl State for {} {{", name);
+    let _ = writeln!(code, "    type Transaction = {};", transaction);
+    let _ = writeln!(code, "}}");
+    match state.group {
+        Some(Group::Wait) => {
+            let _ = writeln!(code, "impl WaitableMarker for {} {{}}", name);
+        }
+        Some(Group::Action) => {
+            let _ = writeln!(code, "impl ActionableMarker for {} {{}}", name);
+        }
+        None => {
+            let _ = writeln!(code, "impl TopLevelMarker for {} {{}}", name);
+            if !state.terminal {
+                let _ = writeln!(code, "impl NonTerminalState for {} {{}}", name);
+            }
+        }
+    }
+    let _ = writeln!(code);
+}
+
+fn write_transaction_item(code: &mut String, definition: &Definition) {
+    let mut variants = vec!["Epsilon".to_string()];
+    variants.extend(
+        definition
+            .states
+            .iter()
+            .filter(|s| !s.fields.is_empty())
+            .map(transaction_of),
+    );
+
+    let _ = writeln!(code, "/// Collection of known Transaction structures.");
+    let _ = writeln!(code, "#[derive(Debug, Clone)]");
+    let _ = writeln!(code, "pub enum TransactionItem {{");
+    for variant in &variants {
+        let _ = writeln!(code, "    /// See [`{0}`]\n    {0}({0}),", variant);
+    }
+    let _ = writeln!(code, "}}");
+    let _ = writeln!(code, "impl TransactionContainer for TransactionItem {{}}\n");
+
+    for variant in &variants {
+        let _ = writeln!(
+            code,
+            "impl From<{0}> for TransactionItem {{
+    fn from(x: {0}) -> Self {{
+        TransactionItem::{0}(x)
+    }}
+}}
+
+impl TryFrom<TransactionItem> for {0} {{
+    type Error = RuntimeConstraintError;
+
+    fn try_from(tc: TransactionItem) -> Result<Self, Self::Error> {{
+        match tc {{
+            TransactionItem::{0}(x) => Ok(x),
+            e => Err((stringify!(TransactionItem::{0}), format!(\"{{:?}}\", e)).into()),
+        }}
+    }}
+}}
+",
+            variant
+        );
+    }
+}
+
+fn write_machine(code: &mut String, definition: &Definition) {
+    let initial = definition
+        .state(&definition.initial)
+        .expect("Validated definition");
+    let _ = writeln!(
+        code,
+        "/// The state machine.
+#[derive(Debug, Clone)]
+pub struct Machine<X>
+where
+    X: TopLevelMarker + State,
+{{
+    /// Field to encode the current state of the machine.
+    pub state: PhantomData<X>,
+    /// Field to store the provided Transaction object as required by the current state.
+    pub transaction: X::Transaction,
+    /// Stack storage service to allow Pushdown and Pullup behaviour.
+    pub storage: StackStorage<TransactionItem>,
+}}
+
+impl<X> StateContainer for Machine<X>
+where
+    X: TopLevelMarker + State,
+{{
+    type State = X;
+}}
+
+impl<X> ServiceCompliance<StackStorage<TransactionItem>> for Machine<X>
+where
+    X: TopLevelMarker + State,
+{{
+    fn get(&self) -> &StackStorage<TransactionItem> {{
+        &self.storage
+    }}
+
+    fn get_mut(&mut self) -> &mut StackStorage<TransactionItem> {{
+        &mut self.storage
+    }}
+}}
+
+impl Machine<{0}> {{
+    /// Build a new state machine residing in the initial state.
+    pub fn new() -> Self {{
+        Machine {{
+            state: PhantomData,
+            transaction: Epsilon,
+            storage: StackStorage {{ tape: vec![] }},
+        }}
+    }}
+}}
+
+impl Default for Machine<{0}> {{
+    fn default() -> Self {{
+        Self::new()
+    }}
+}}
+",
+        type_of(initial)
+    );
+}
+
+fn write_edge(code: &mut String, kind: EdgeKind, from: &str, to: &str) {
+    let _ = match kind {
+        EdgeKind::Transition => writeln!(
+            code,
+            "impl TransitionFrom<Machine<{0}>> for Machine<{1}> {{
+    fn transition_from(
+        old: Machine<{0}>,
+        t: <Self::State as State>::Transaction,
+        _: Checked,
+    ) -> Self {{
+        Machine {{
+            state: PhantomData,
+            transaction: t,
+            storage: old.storage,
+        }}
+    }}
+}}
+",
+            from, to
+        ),
+        EdgeKind::Pushdown => writeln!(
+            code,
+            "impl PushdownFrom<Machine<{0}>, TransactionItem> for Machine<{1}> {{
+    fn pushdown_from(
+        mut old: Machine<{0}>,
+        t: <Self::State as State>::Transaction,
+        _: Checked,
+    ) -> Self {{
+        let old_transaction: TransactionItem = pack_transaction(old.transaction);
+        old.storage.push(old_transaction).expect(\"Never type triggered!\");
+        Machine {{
+            state: PhantomData,
+            transaction: t,
+            storage: old.storage,
+        }}
+    }}
+}}
+",
+            from, to
+        ),
+        EdgeKind::Pullup => writeln!(
+            code,
+            "impl PullupFrom<Machine<{0}>, TransactionItem> for Machine<{1}> {{
+    fn pullup_from(mut old: Machine<{0}>, _: Checked) -> Result<Self, MachineError> {{
+        let old_transaction = ServiceCompliance::<StackStorage<TransactionItem>>::get_mut(&mut old)
+            .pop()
+            .context(ErrorKind::LogicError, &old)
+            .and_then(|item| unpack_transaction(item).context(ErrorKind::ConstraintError, &old))?;
+        Ok(Machine {{
+            state: PhantomData,
+            transaction: old_transaction,
+            storage: old.storage,
+        }})
+    }}
+}}
+",
+            from, to
+        ),
+    };
+}
+
+fn write_registry(code: &mut String, definition: &Definition) {
+    let _ = writeln!(code, "/// Identification of each top-level state.");
+    let _ = writeln!(code, "#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]");
+    let _ = writeln!(code, "pub enum StateId {{");
+    for state in &definition.states {
+        let _ = writeln!(code, "    /// See [`{0}`].\n    {0},", state.name);
+    }
+    let _ = writeln!(code, "}}\n");
+
+    let all: Vec<String> = definition
+        .states
+        .iter()
+        .map(|s| format!("StateId::{}", s.name))
+        .collect();
+    let terminal: Vec<String> = definition
+        .states
+        .iter()
+        .filter(|s| s.terminal)
+        .map(|s| format!("StateId::{}", s.name))
+        .collect();
+    let _ = writeln!(
+        code,
+        "impl StateSet for StateId {{
+    const ALL: &'static [StateId] = &[{}];
+
+    const INITIAL: StateId = StateId::{};
+
+    fn is_terminal(self) -> bool {{
+        [{}].contains(&self)
+    }}
+}}
+",
+        all.join(", "),
+        definition.initial,
+        terminal.join(", ")
+    );
+
+    let _ = writeln!(code, "impl ::std::fmt::Display for StateId {{");
+    let _ = writeln!(
+        code,
+        "    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {{"
+    );
+    let _ = writeln!(code, "        let name = match *self {{");
+    for state in &definition.states {
+        let _ = writeln!(
+            code,
+            "            StateId::{} => {:?},",
+            state.name,
+            type_of(state)
+        );
+    }
+    let _ = writeln!(code, "        }};");
+    let _ = writeln!(code, "        f.write_str(name)");
+    let _ = writeln!(code, "    }}");
+    let _ = writeln!(code, "}}\n");
+
+    let _ = writeln!(code, "/// Registry of all implemented edges.");
+    let _ = writeln!(code, "pub const TRANSITIONS: &[Edge<StateId>] = &[");
+    for edge in &definition.edges {
+        let _ = writeln!(
+            code,
+            "    Edge {{
+        kind: EdgeKind::{:?},
+        from: StateId::{},
+        to: StateId::{},
+    }},",
+            edge.kind, edge.from, edge.to
+        );
+    }
+    let _ = writeln!(code, "];");
+}
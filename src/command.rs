@@ -0,0 +1,160 @@
+//! Textual commands for driving an [`AnyMachine`](::dynamic::AnyMachine).
+//!
+//! The command language is line based, one command per line:
+//!
+//! - `transition <state> [transaction]`
+//! - `push <state> [transaction]`
+//! - `pullup [state]`
+//! - `finish`
+//!
+//! States are written either in full (eg `Action<Print>`) or by their inner name
+//! (eg `print`). A transaction is written like [`TransactionItem`] displays itself,
+//! eg `Print "Hello"`. A bare quoted text is shorthand for a [`PrintTransaction`] and
+//! an omitted transaction means [`Epsilon`].
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use function::error::RuntimeConstraintError;
+use function::helper::unquote;
+use registry::{Edge, EdgeKind, StateId};
+use transaction::{Epsilon, PrintTransaction, TransactionItem};
+use TRANSITIONS;
+
+/// One requested move of the state machine.
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// Transition into the provided state, see [`EdgeKind::Transition`].
+    Transition(StateId, TransactionItem),
+    /// Pushdown into the provided state, see [`EdgeKind::Pushdown`].
+    Pushdown(StateId, TransactionItem),
+    /// Pullup into the provided state, or whichever state is possible when omitted.
+    /// See [`EdgeKind::Pullup`].
+    Pullup(Option<StateId>),
+}
+
+impl Command {
+    /// Find the edge, starting from the provided state, which executes this command.
+    pub fn edge(&self, from: StateId) -> Result<Edge, RuntimeConstraintError> {
+        let (kind, to) = match *self {
+            Command::Transition(to, _) => (EdgeKind::Transition, Some(to)),
+            Command::Pushdown(to, _) => (EdgeKind::Pushdown, Some(to)),
+            Command::Pullup(to) => (EdgeKind::Pullup, to),
+        };
+        let candidates: Vec<Edge> = TRANSITIONS
+            .iter()
+            .cloned()
+            .filter(|e| e.kind == kind && e.from == from)
+            .filter(|e| to.is_none() || to == Some(e.to))
+            .collect();
+        match candidates.len() {
+            1 => Ok(candidates[0]),
+            _ => {
+                let expected = format!("exactly one legal move from {}", from);
+                let factual = format!("{} moves for {}", candidates.len(), self);
+                Err((expected, factual).into())
+            }
+        }
+    }
+
+    /// The Transaction provided with this command, if any.
+    pub fn transaction(&self) -> Option<&TransactionItem> {
+        match *self {
+            Command::Transition(_, ref t) | Command::Pushdown(_, ref t) => Some(t),
+            Command::Pullup(_) => None,
+        }
+    }
+
+    /// Split into the Transaction provided with this command, if any.
+    pub fn into_transaction(self) -> Option<TransactionItem> {
+        match self {
+            Command::Transition(_, t) | Command::Pushdown(_, t) => Some(t),
+            Command::Pullup(_) => None,
+        }
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Command::Transition(to, ref t) => write!(f, "transition {} {}", to, t),
+            Command::Pushdown(to, ref t) => write!(f, "push {} {}", to, t),
+            Command::Pullup(Some(to)) => write!(f, "pullup {}", to),
+            Command::Pullup(None) => write!(f, "pullup"),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = RuntimeConstraintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (verb, rest) = split_word(s.trim());
+        match verb {
+            "transition" => {
+                let (state, rest) = split_word(rest);
+                Ok(Command::Transition(parse_state(state)?, parse_transaction(rest)?))
+            }
+            "push" | "pushdown" => {
+                let (state, rest) = split_word(rest);
+                Ok(Command::Pushdown(parse_state(state)?, parse_transaction(rest)?))
+            }
+            "pullup" if rest.is_empty() => Ok(Command::Pullup(None)),
+            "pullup" => Ok(Command::Pullup(Some(parse_state(rest)?))),
+            "finish" if rest.is_empty() => {
+                Ok(Command::Transition(StateId::Finished, Epsilon.into()))
+            }
+            _ => {
+                let expected = "a transition, push, pullup or finish command";
+                Err((expected, s).into())
+            }
+        }
+    }
+}
+
+/// Describe how the provided edge is written as a command.
+pub fn usage(edge: Edge) -> String {
+    let to = inner_name(edge.to);
+    match edge.kind {
+        EdgeKind::Transition if edge.to == StateId::Finished => "finish".to_string(),
+        EdgeKind::Transition => format!("transition {} [transaction]", to),
+        EdgeKind::Pushdown => format!("push {} [transaction]", to),
+        EdgeKind::Pullup => format!("pullup {}", to),
+    }
+}
+
+/// Lowercase name of the state, without its top-level wrapper.
+fn inner_name(state: StateId) -> String {
+    let name = state.to_string();
+    let inner = match (name.find('<'), name.rfind('>')) {
+        (Some(start), Some(end)) => name[start + 1..end].to_string(),
+        _ => name,
+    };
+    inner.to_lowercase()
+}
+
+fn split_word(s: &str) -> (&str, &str) {
+    match s.find(char::is_whitespace) {
+        Some(idx) => (&s[..idx], s[idx..].trim()),
+        None => (s, ""),
+    }
+}
+
+/// Parse a state written in full or by its inner name.
+pub fn parse_state(s: &str) -> Result<StateId, RuntimeConstraintError> {
+    StateId::ALL
+        .iter()
+        .cloned()
+        .find(|state| inner_name(*state) == s.to_lowercase())
+        .map_or_else(|| s.parse(), Ok)
+}
+
+fn parse_transaction(s: &str) -> Result<TransactionItem, RuntimeConstraintError> {
+    if s.is_empty() {
+        Ok(Epsilon.into())
+    } else if s.starts_with('"') {
+        Ok(PrintTransaction(unquote(s)?).into())
+    } else {
+        s.parse()
+    }
+}
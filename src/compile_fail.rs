@@ -0,0 +1,222 @@
+//! Illegal uses of the state machine, which MUST NOT compile.
+//!
+//! Each case is paired with its legal counterpart, so a case can only pass by failing
+//! on the guarantee it describes. The expected error code is checked as well.
+//!
+//! # Transition into a state without transition from the current state
+//!
+//! ```
+//! # use automaton_test::Machine;
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::*;
+//! # use automaton_test::transaction::Epsilon;
+//! let start = Machine::<Wait<Start>>::new();
+//! let input: Machine<Wait<Input>> = start.transition(Epsilon).unwrap();
+//! ```
+//!
+//! ```compile_fail,E0277
+//! # use automaton_test::Machine;
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::*;
+//! # use automaton_test::transaction::Epsilon;
+//! let start = Machine::<Wait<Start>>::new();
+//! let finished: Machine<Finished> = start.transition(Epsilon).unwrap();
+//! ```
+//!
+//! # Pushdown into a state without pushdown from the current state
+//!
+//! ```
+//! # use automaton_test::Machine;
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::*;
+//! # use automaton_test::transaction::{Epsilon, PrintTransaction, TransactionItem};
+//! # let start = Machine::<Wait<Start>>::new();
+//! # let input: Machine<Wait<Input>> = start.transition(Epsilon).unwrap();
+//! let print: Machine<Action<Print>> =
+//!     PushdownInto::<_, TransactionItem>::pushdown(input, PrintTransaction("Hello".into()))
+//!         .unwrap();
+//! ```
+//!
+//! ```compile_fail,E0277
+//! # use automaton_test::Machine;
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::*;
+//! # use automaton_test::transaction::{Epsilon, TransactionItem};
+//! # let start = Machine::<Wait<Start>>::new();
+//! # let input: Machine<Wait<Input>> = start.transition(Epsilon).unwrap();
+//! let load: Machine<Action<Load>> =
+//!     PushdownInto::<_, TransactionItem>::pushdown(input, Epsilon).unwrap();
+//! ```
+//!
+//! # Pullup out of a state which was never pushed down into
+//!
+//! ```
+//! # use automaton_test::Machine;
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::*;
+//! # use automaton_test::transaction::{Epsilon, PrintTransaction, TransactionItem};
+//! # let start = Machine::<Wait<Start>>::new();
+//! # let input: Machine<Wait<Input>> = start.transition(Epsilon).unwrap();
+//! # let print: Machine<Action<Print>> =
+//! #     PushdownInto::<_, TransactionItem>::pushdown(input, PrintTransaction("Hello".into()))
+//! #         .unwrap();
+//! let input: Machine<Wait<Input>> = PullupInto::<_, TransactionItem>::pullup(print).unwrap();
+//! ```
+//!
+//! ```compile_fail,E0277
+//! # use automaton_test::Machine;
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::*;
+//! # use automaton_test::transaction::{Epsilon, TransactionItem};
+//! # let start = Machine::<Wait<Start>>::new();
+//! # let input: Machine<Wait<Input>> = start.transition(Epsilon).unwrap();
+//! let start: Machine<Wait<Start>> = PullupInto::<_, TransactionItem>::pullup(input).unwrap();
+//! ```
+//!
+//! # Transaction not matching the next state
+//!
+//! ```compile_fail,E0271
+//! # use automaton_test::Machine;
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::*;
+//! # use automaton_test::transaction::{Epsilon, TransactionItem};
+//! # let start = Machine::<Wait<Start>>::new();
+//! # let input: Machine<Wait<Input>> = start.transition(Epsilon).unwrap();
+//! let print: Machine<Action<Print>> =
+//!     PushdownInto::<_, TransactionItem>::pushdown(input, Epsilon).unwrap();
+//! ```
+//!
+//! # Edges out of a terminal state
+//!
+//! Declaring an edge out of [`Finished`](::state::Finished) is rejected, even on
+//! machines defined outside of this crate.
+//!
+//! ```
+//! # use std::marker::PhantomData;
+//! # use automaton_test::function::{State, StateContainer};
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::{Checked, TransitionFrom, TransitionInto};
+//! # use automaton_test::transaction::Epsilon;
+//! #[derive(Debug, Clone)]
+//! struct Custom<X>(PhantomData<X>);
+//! impl<X> StateContainer for Custom<X> {
+//!     type State = X;
+//! }
+//!
+//! impl TransitionFrom<Custom<Wait<Input>>> for Custom<Finished> {
+//!     fn transition_from(
+//!         _: Custom<Wait<Input>>,
+//!         _: <Self::State as State>::Transaction,
+//!         _: Checked,
+//!     ) -> Self {
+//!         Custom(PhantomData)
+//!     }
+//! }
+//! # let input = Custom::<Wait<Input>>(PhantomData);
+//! # let finished: Custom<Finished> = input.transition(Epsilon).unwrap();
+//! ```
+//!
+//! ```compile_fail,E0277
+//! # use std::marker::PhantomData;
+//! # use automaton_test::function::{State, StateContainer};
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::{Checked, TransitionFrom};
+//! struct Custom<X>(PhantomData<X>);
+//! impl<X> StateContainer for Custom<X> {
+//!     type State = X;
+//! }
+//!
+//! impl TransitionFrom<Custom<Finished>> for Custom<Wait<Input>> {
+//!     fn transition_from(
+//!         _: Custom<Finished>,
+//!         _: <Self::State as State>::Transaction,
+//!         _: Checked,
+//!     ) -> Self {
+//!         Custom(PhantomData)
+//!     }
+//! }
+//! ```
+//!
+//! Transitioning a finished machine is rejected as well.
+//!
+//! ```compile_fail,E0599
+//! # use automaton_test::Machine;
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::*;
+//! # use automaton_test::transaction::Epsilon;
+//! # let start = Machine::<Wait<Start>>::new();
+//! # let input: Machine<Wait<Input>> = start.transition(Epsilon).unwrap();
+//! let finished: Machine<Finished> = input.transition(Epsilon).unwrap();
+//! let again: Machine<Finished> = finished.transition(Epsilon).unwrap();
+//! ```
+//!
+//! Pushdown and pullup out of a finished machine are rejected too, where the same
+//! moves out of a waiting or acting machine compile.
+//!
+//! ```
+//! # use automaton_test::Machine;
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::*;
+//! # use automaton_test::transaction::{Epsilon, PrintTransaction, TransactionItem};
+//! # let start = Machine::<Wait<Start>>::new();
+//! # let input: Machine<Wait<Input>> = start.transition(Epsilon).unwrap();
+//! let print: Machine<Action<Print>> =
+//!     PushdownInto::<_, TransactionItem>::pushdown(input, PrintTransaction("Hello".into()))
+//!         .unwrap();
+//! let input: Machine<Wait<Input>> = PullupInto::<_, TransactionItem>::pullup(print).unwrap();
+//! ```
+//!
+//! ```compile_fail,E0277
+//! # use automaton_test::Machine;
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::*;
+//! # use automaton_test::transaction::{Epsilon, PrintTransaction, TransactionItem};
+//! # let start = Machine::<Wait<Start>>::new();
+//! # let input: Machine<Wait<Input>> = start.transition(Epsilon).unwrap();
+//! let finished: Machine<Finished> = input.transition(Epsilon).unwrap();
+//! let print: Machine<Action<Print>> =
+//!     PushdownInto::<_, TransactionItem>::pushdown(finished, PrintTransaction("Hello".into()))
+//!         .unwrap();
+//! ```
+//!
+//! ```compile_fail,E0277
+//! # use automaton_test::Machine;
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::*;
+//! # use automaton_test::transaction::{Epsilon, TransactionItem};
+//! # let start = Machine::<Wait<Start>>::new();
+//! # let input: Machine<Wait<Input>> = start.transition(Epsilon).unwrap();
+//! let finished: Machine<Finished> = input.transition(Epsilon).unwrap();
+//! let input: Machine<Wait<Input>> = PullupInto::<_, TransactionItem>::pullup(finished).unwrap();
+//! ```
+//!
+//! # Transition skipping validation
+//!
+//! The `*From` methods can only be called through the `*Into` helpers, which validate
+//! the Transaction first.
+//!
+//! ```
+//! # use automaton_test::Machine;
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::*;
+//! # use automaton_test::transaction::{Epsilon, PrintTransaction, TransactionItem};
+//! # let start = Machine::<Wait<Start>>::new();
+//! # let input: Machine<Wait<Input>> = start.transition(Epsilon).unwrap();
+//! let result: Result<Machine<Action<Print>>, _> =
+//!     PushdownInto::<_, TransactionItem>::pushdown(input, PrintTransaction("".into()));
+//! assert!(result.is_err());
+//! ```
+//!
+//! ```compile_fail,E0423
+//! # use automaton_test::Machine;
+//! # use automaton_test::state::*;
+//! # use automaton_test::stm::*;
+//! # use automaton_test::transaction::{Epsilon, PrintTransaction, TransactionItem};
+//! # let start = Machine::<Wait<Start>>::new();
+//! # let input: Machine<Wait<Input>> = start.transition(Epsilon).unwrap();
+//! let print: Machine<Action<Print>> = PushdownFrom::<_, TransactionItem>::pushdown_from(
+//!     input,
+//!     PrintTransaction("".into()),
+//!     Checked(()),
+//! );
+//! ```
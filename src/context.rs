@@ -0,0 +1,102 @@
+//! User defined data carried by the state machine.
+//!
+//! A [`Machine`] holds one context value next to its services. The value is moved
+//! along on every transition, pushdown and pullup, so it outlives the Transaction of
+//! each single state. Since the context is part of the machine, it's also part of the
+//! snapshot within each [`MachineError`](::function::error::MachineError).
+//!
+//! The [`Context`] trait hooks into each applied edge:
+//! 1. [`Context::guard`] decides whether the edge may be applied at all;
+//! 2. [`Context::on_exit`] runs before the current state is left;
+//! 3. [`Context::on_entry`] runs after the next state is entered.
+//!
+//! ```
+//! use automaton_test::context::Context;
+//! use automaton_test::function::error::RuntimeConstraintError;
+//! use automaton_test::registry::Edge;
+//! use automaton_test::state::*;
+//! use automaton_test::stm::*;
+//! use automaton_test::transaction::{Epsilon, PrintTransaction, TransactionItem};
+//! use automaton_test::Machine;
+//!
+//! /// Allows a limited amount of prints and counts the entered states.
+//! #[derive(Debug, Clone)]
+//! struct Budget {
+//!     prints: usize,
+//!     entered: usize,
+//! }
+//!
+//! impl Context for Budget {
+//!     fn guard(
+//!         &self,
+//!         edge: Edge,
+//!         _: Option<&TransactionItem>,
+//!     ) -> Result<(), RuntimeConstraintError> {
+//!         match (edge, self.prints) {
+//!             (Edge::INPUT_PUSHDOWN_PRINT, 0) => Err(("a remaining print", "none").into()),
+//!             _ => Ok(()),
+//!         }
+//!     }
+//!
+//!     fn on_exit(&mut self, edge: Edge) {
+//!         if edge == Edge::INPUT_PUSHDOWN_PRINT {
+//!             self.prints -= 1;
+//!         }
+//!     }
+//!
+//!     fn on_entry(&mut self, _: Edge) {
+//!         self.entered += 1;
+//!     }
+//! }
+//!
+//! let start = Machine::<Wait<Start>, _>::with_context(Budget { prints: 1, entered: 0 });
+//! let input: Machine<Wait<Input>, _> = start.transition(Epsilon).unwrap();
+//! let print: Machine<Action<Print>, _> =
+//!     PushdownInto::<_, TransactionItem>::pushdown(input, PrintTransaction("Hi".into()))
+//!         .unwrap();
+//! let input: Machine<Wait<Input>, _> = PullupInto::<_, TransactionItem>::pullup(print).unwrap();
+//! assert_eq!(input.context.prints, 0);
+//! assert_eq!(input.context.entered, 3);
+//!
+//! // The budget is spent, the snapshot of the error holds the context.
+//! let result: Result<Machine<Action<Print>, _>, _> =
+//!     PushdownInto::<_, TransactionItem>::pushdown(input, PrintTransaction("Hi".into()));
+//! let error = result.unwrap_err();
+//! assert!(format!("{:?}", error.snapshot()).contains("prints: 0"));
+//! ```
+//!
+//! [`Machine`]: ::Machine
+
+use std::fmt::Debug;
+
+use function::error::RuntimeConstraintError;
+use registry::Edge;
+use transaction::TransactionItem;
+
+/// Trait for user defined data stored within the state machine.
+///
+/// Each hook has a default implementation which does nothing, so plain data only
+/// requires an empty implementation.
+pub trait Context: Clone + Debug + Send + Sync + 'static {
+    /// Verify that the provided edge may be applied onto the machine.
+    ///
+    /// The Transaction for the next state is provided, except for pullup edges which
+    /// restore it from storage. The machine is left untouched when the guard fails.
+    /// The default implementation accepts every edge.
+    fn guard(
+        &self,
+        edge: Edge,
+        transaction: Option<&TransactionItem>,
+    ) -> Result<(), RuntimeConstraintError> {
+        Ok(())
+    }
+
+    /// Action executed when the state at the start of the provided edge is left.
+    fn on_exit(&mut self, edge: Edge) {}
+
+    /// Action executed when the state at the end of the provided edge is entered.
+    fn on_entry(&mut self, edge: Edge) {}
+}
+
+/// The context of machines which don't carry any user defined data.
+impl Context for () {}
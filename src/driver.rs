@@ -0,0 +1,180 @@
+//! Run-to-completion driving of [`Action`] states.
+//!
+//! An Action state represents work in progress. The work itself is performed by an
+//! executor, implementing [`Execute`] for each action it supports. After each run the
+//! executor decides the next [`Move`], which the driver applies until the machine no
+//! longer resides in an Action state.
+//!
+//! ```
+//! use automaton_test::driver::{Execute, Move};
+//! use automaton_test::dynamic::AnyMachine;
+//! use automaton_test::function::error::MachineError;
+//! use automaton_test::registry::StateId;
+//! use automaton_test::state::{Action, Load, Print};
+//! use automaton_test::transaction::Epsilon;
+//! use automaton_test::Machine;
+//!
+//! #[derive(Default)]
+//! struct Printer {
+//!     loading: u32,
+//!     loaded: bool,
+//!     output: Vec<String>,
+//! }
+//!
+//! impl Execute<Print> for Printer {
+//!     fn run(&mut self, machine: &Machine<Action<Print>>) -> Result<Move, MachineError> {
+//!         if !self.loaded {
+//!             return Ok(Move::Pushdown(StateId::ActionLoad, Epsilon.into()));
+//!         }
+//!         self.output.push(machine.transaction.0.clone());
+//!         Ok(Move::Pullup)
+//!     }
+//! }
+//!
+//! impl Execute<Load> for Printer {
+//!     fn run(&mut self, _: &Machine<Action<Load>>) -> Result<Move, MachineError> {
+//!         self.loading += 1;
+//!         self.loaded = self.loading == 3;
+//!         Ok(if self.loaded { Move::Pullup } else { Move::Stay })
+//!     }
+//! }
+//!
+//! let mut printer = Printer::default();
+//! let mut machine = AnyMachine::default();
+//! machine.execute("transition input".parse().unwrap()).unwrap();
+//! machine.execute("push print \"Hello\"".parse().unwrap()).unwrap();
+//!
+//! let edges = machine.run_to_completion(&mut printer).unwrap();
+//! assert_eq!(edges.len(), 3);
+//! assert_eq!(machine.state(), StateId::WaitInput);
+//! assert_eq!(printer.output, vec!["Hello".to_string()]);
+//! ```
+//!
+//! [`Action`]: ::state::Action
+
+use command::Command;
+use context::Context;
+use dynamic::AnyMachine;
+use function::error::MachineError;
+use function::State;
+use marker::ActionableMarker;
+use registry::{Edge, StateId};
+use state::{Action, Load, Print};
+use transaction::TransactionItem;
+use Machine;
+
+/// Next move of the machine, decided after performing the work of an action.
+#[derive(Debug, Clone)]
+pub enum Move {
+    /// Pushdown into the provided action, with its Transaction.
+    Pushdown(StateId, TransactionItem),
+    /// The work is done, pullup into the state which pushed down.
+    Pullup,
+    /// The work is still in progress, run the same action again.
+    Stay,
+}
+
+impl Move {
+    /// The command applying this move, if the machine moves at all.
+    pub fn into_command(self) -> Option<Command> {
+        match self {
+            Move::Pushdown(to, t) => Some(Command::Pushdown(to, t)),
+            Move::Pullup => Some(Command::Pullup(None)),
+            Move::Stay => None,
+        }
+    }
+}
+
+/// Types which perform the work of action `A`, onto machines carrying context `C`.
+///
+/// One type is expected to implement this trait for each action it drives, see
+/// [`AnyMachine::run_to_completion`].
+pub trait Execute<A, C = ()>
+where
+    A: ActionableMarker + State,
+    C: Context,
+{
+    /// Perform (part of) the work of the action the machine resides in.
+    ///
+    /// # Errors
+    /// Failing work is reported with a snapshot of the provided machine, see
+    /// [`SnapshottedErrorExt`](::function::error::SnapshottedErrorExt).
+    fn run(&mut self, machine: &Machine<Action<A>, C>) -> Result<Move, MachineError>;
+}
+
+impl<C> AnyMachine<C>
+where
+    C: Context,
+{
+    /// Run the executor onto each Action state the machine resides in, until it's back in
+    /// a [`Wait`](::state::Wait) state or `Finished`.
+    ///
+    /// The executor MUST eventually move, since [`Move::Stay`] runs the same action again.
+    /// The applied edges are returned in order.
+    ///
+    /// # Errors
+    /// A failing run or move is returned, the machine is left in the state it failed in.
+    ///
+    /// ```
+    /// use automaton_test::context::Context;
+    /// use automaton_test::driver::{Execute, Move};
+    /// use automaton_test::dynamic::AnyMachine;
+    /// use automaton_test::function::error::MachineError;
+    /// use automaton_test::registry::{Edge, StateId};
+    /// use automaton_test::state::{Action, Load, Print};
+    /// use automaton_test::transaction::Epsilon;
+    /// use automaton_test::Machine;
+    ///
+    /// /// Counts the entered states.
+    /// #[derive(Debug, Clone, Default)]
+    /// struct Entered(usize);
+    ///
+    /// impl Context for Entered {
+    ///     fn on_entry(&mut self, _: Edge) {
+    ///         self.0 += 1;
+    ///     }
+    /// }
+    ///
+    /// /// Loads once before printing, which the context tells.
+    /// struct Printer;
+    ///
+    /// impl Execute<Print, Entered> for Printer {
+    ///     fn run(&mut self, m: &Machine<Action<Print>, Entered>) -> Result<Move, MachineError> {
+    ///         if m.context.0 < 3 {
+    ///             return Ok(Move::Pushdown(StateId::ActionLoad, Epsilon.into()));
+    ///         }
+    ///         Ok(Move::Pullup)
+    ///     }
+    /// }
+    ///
+    /// impl Execute<Load, Entered> for Printer {
+    ///     fn run(&mut self, _: &Machine<Action<Load>, Entered>) -> Result<Move, MachineError> {
+    ///         Ok(Move::Pullup)
+    ///     }
+    /// }
+    ///
+    /// let mut machine = AnyMachine::WaitStart(Machine::with_context(Entered::default()));
+    /// machine.execute("transition input".parse().unwrap()).unwrap();
+    /// machine.execute("push print \"Hello\"".parse().unwrap()).unwrap();
+    ///
+    /// assert_eq!(machine.run_to_completion(&mut Printer).unwrap().len(), 3);
+    /// assert_eq!(machine.context().0, 5);
+    /// ```
+    pub fn run_to_completion<E>(&mut self, executor: &mut E) -> Result<Vec<Edge>, MachineError>
+    where
+        E: Execute<Load, C> + Execute<Print, C>,
+    {
+        let mut edges = vec![];
+        loop {
+            let next = match *self {
+                AnyMachine::ActionLoad(ref m) => Execute::<Load, C>::run(executor, m)?,
+                AnyMachine::ActionPrint(ref m) => Execute::<Print, C>::run(executor, m)?,
+                // Every other state is a Wait state or Finished.
+                _ => return Ok(edges),
+            };
+            if let Some(command) = next.into_command() {
+                edges.push(self.execute(command)?);
+            }
+        }
+    }
+}
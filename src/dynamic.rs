@@ -0,0 +1,553 @@
+//! Types for driving a state machine whose state is only known at runtime.
+//!
+//! The typestate [`Machine`] verifies each transition at compile time, which makes it
+//! impossible to decide the next step from data (eg user input or a stored journal).
+//! [`AnyMachine`] wraps each possible [`Machine`] and dispatches edges, described by the
+//! [registry](::registry), onto the real transition implementations.
+//!
+//! [`Machine`]: ::Machine
+
+use std::convert::TryInto;
+use std::fmt::Debug;
+use std::mem;
+
+use failure::Fail;
+
+use self::error::ReplayError;
+use acl::Actor;
+use command::Command;
+use context::Context;
+use function::error::{ErrorKind, MachineError, RuntimeConstraintError, SnapshottedErrorExt};
+use function::helper::unpack_transaction;
+use function::{ServiceCompliance, StateContainer, Validate};
+use instrument::Spans;
+use marker::Transaction;
+use middleware::{Chain, Step};
+use registry::{Edge, EdgeKind, StateId};
+use service::error::StackPopError;
+use service::{Journal, Rng, StackStorage, Stopwatch};
+use state::*;
+use stm::{Checked, PullupFrom, PushdownFrom, TransitionFrom};
+use trace::Trace;
+use transaction::{Epsilon, PrintTransaction, TransactionItem};
+use {Machine, TRANSITIONS};
+
+pub mod error {
+    //! Types for simplifying error handling syntax.
+
+    use function::error::MachineError;
+
+    /// Error thrown when a [`Journal`](::service::Journal) can not be replayed.
+    #[derive(Debug, Fail)]
+    #[fail(display = "Replaying the journal failed at event {}", index)]
+    pub struct ReplayError {
+        /// Index of the first event which could not be applied.
+        pub index: usize,
+        /// Reason the event could not be applied.
+        #[cause]
+        pub inner: MachineError,
+    }
+}
+
+/// State machine residing in any of the known states.
+#[derive(Debug, Clone)]
+pub enum AnyMachine<C = ()>
+where
+    C: Context,
+{
+    /// See [`StateId::WaitStart`].
+    WaitStart(Machine<Wait<Start>, C>),
+    /// See [`StateId::WaitInput`].
+    WaitInput(Machine<Wait<Input>, C>),
+    /// See [`StateId::ActionLoad`].
+    ActionLoad(Machine<Action<Load>, C>),
+    /// See [`StateId::ActionPrint`].
+    ActionPrint(Machine<Action<Print>, C>),
+    /// See [`StateId::Finished`].
+    Finished(Machine<Finished, C>),
+    /// Placeholder while an edge is applied, left behind when applying the edge panicked.
+    #[doc(hidden)]
+    Poisoned,
+}
+
+impl<C> Default for AnyMachine<C>
+where
+    C: Context + Default,
+{
+    fn default() -> Self {
+        AnyMachine::WaitStart(Machine::default())
+    }
+}
+
+impl<C> AnyMachine<C>
+where
+    C: Context,
+{
+    /// Identification of the current state.
+    pub fn state(&self) -> StateId {
+        match *self {
+            AnyMachine::WaitStart(_) => StateId::WaitStart,
+            AnyMachine::WaitInput(_) => StateId::WaitInput,
+            AnyMachine::ActionLoad(_) => StateId::ActionLoad,
+            AnyMachine::ActionPrint(_) => StateId::ActionPrint,
+            AnyMachine::Finished(_) => StateId::Finished,
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// The Transaction held by the current state.
+    pub fn transaction(&self) -> TransactionItem {
+        match *self {
+            AnyMachine::WaitStart(ref m) => m.transaction.into(),
+            AnyMachine::WaitInput(ref m) => m.transaction.into(),
+            AnyMachine::ActionLoad(ref m) => m.transaction.into(),
+            AnyMachine::ActionPrint(ref m) => m.transaction.clone().into(),
+            AnyMachine::Finished(ref m) => m.transaction.into(),
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// The stack storage service of the current state.
+    pub fn storage(&self) -> &StackStorage<TransactionItem> {
+        match *self {
+            AnyMachine::WaitStart(ref m) => &m.storage,
+            AnyMachine::WaitInput(ref m) => &m.storage,
+            AnyMachine::ActionLoad(ref m) => &m.storage,
+            AnyMachine::ActionPrint(ref m) => &m.storage,
+            AnyMachine::Finished(ref m) => &m.storage,
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// The journal service of the current state.
+    pub fn journal(&self) -> &Journal {
+        match *self {
+            AnyMachine::WaitStart(ref m) => &m.journal,
+            AnyMachine::WaitInput(ref m) => &m.journal,
+            AnyMachine::ActionLoad(ref m) => &m.journal,
+            AnyMachine::ActionPrint(ref m) => &m.journal,
+            AnyMachine::Finished(ref m) => &m.journal,
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// The trace service of the current state.
+    pub fn trace(&self) -> &Trace {
+        match *self {
+            AnyMachine::WaitStart(ref m) => &m.trace,
+            AnyMachine::WaitInput(ref m) => &m.trace,
+            AnyMachine::ActionLoad(ref m) => &m.trace,
+            AnyMachine::ActionPrint(ref m) => &m.trace,
+            AnyMachine::Finished(ref m) => &m.trace,
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// The spans of the `tracing` feature, see [`Spans`].
+    pub(crate) fn spans(&self) -> &Spans {
+        match *self {
+            AnyMachine::WaitStart(ref m) => &m.spans,
+            AnyMachine::WaitInput(ref m) => &m.spans,
+            AnyMachine::ActionLoad(ref m) => &m.spans,
+            AnyMachine::ActionPrint(ref m) => &m.spans,
+            AnyMachine::Finished(ref m) => &m.spans,
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// The stopwatch service of the current state.
+    pub fn stopwatch(&self) -> &Stopwatch {
+        match *self {
+            AnyMachine::WaitStart(ref m) => &m.stopwatch,
+            AnyMachine::WaitInput(ref m) => &m.stopwatch,
+            AnyMachine::ActionLoad(ref m) => &m.stopwatch,
+            AnyMachine::ActionPrint(ref m) => &m.stopwatch,
+            AnyMachine::Finished(ref m) => &m.stopwatch,
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// Mutable access to the stopwatch service of the current state.
+    pub fn stopwatch_mut(&mut self) -> &mut Stopwatch {
+        match *self {
+            AnyMachine::WaitStart(ref mut m) => &mut m.stopwatch,
+            AnyMachine::WaitInput(ref mut m) => &mut m.stopwatch,
+            AnyMachine::ActionLoad(ref mut m) => &mut m.stopwatch,
+            AnyMachine::ActionPrint(ref mut m) => &mut m.stopwatch,
+            AnyMachine::Finished(ref mut m) => &mut m.stopwatch,
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// The middleware service of the current state.
+    pub fn middleware(&self) -> &Chain<C> {
+        match *self {
+            AnyMachine::WaitStart(ref m) => &m.middleware,
+            AnyMachine::WaitInput(ref m) => &m.middleware,
+            AnyMachine::ActionLoad(ref m) => &m.middleware,
+            AnyMachine::ActionPrint(ref m) => &m.middleware,
+            AnyMachine::Finished(ref m) => &m.middleware,
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// Mutable access to the middleware service of the current state, to install layers.
+    pub fn middleware_mut(&mut self) -> &mut Chain<C> {
+        match *self {
+            AnyMachine::WaitStart(ref mut m) => &mut m.middleware,
+            AnyMachine::WaitInput(ref mut m) => &mut m.middleware,
+            AnyMachine::ActionLoad(ref mut m) => &mut m.middleware,
+            AnyMachine::ActionPrint(ref mut m) => &mut m.middleware,
+            AnyMachine::Finished(ref mut m) => &mut m.middleware,
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// The random number generator service of the current state.
+    pub fn rng(&self) -> &Rng {
+        match *self {
+            AnyMachine::WaitStart(ref m) => &m.rng,
+            AnyMachine::WaitInput(ref m) => &m.rng,
+            AnyMachine::ActionLoad(ref m) => &m.rng,
+            AnyMachine::ActionPrint(ref m) => &m.rng,
+            AnyMachine::Finished(ref m) => &m.rng,
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// Mutable access to the random number generator service of the current state.
+    pub fn rng_mut(&mut self) -> &mut Rng {
+        match *self {
+            AnyMachine::WaitStart(ref mut m) => &mut m.rng,
+            AnyMachine::WaitInput(ref mut m) => &mut m.rng,
+            AnyMachine::ActionLoad(ref mut m) => &mut m.rng,
+            AnyMachine::ActionPrint(ref mut m) => &mut m.rng,
+            AnyMachine::Finished(ref mut m) => &mut m.rng,
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// The user defined context of the current state.
+    pub fn context(&self) -> &C {
+        match *self {
+            AnyMachine::WaitStart(ref m) => &m.context,
+            AnyMachine::WaitInput(ref m) => &m.context,
+            AnyMachine::ActionLoad(ref m) => &m.context,
+            AnyMachine::ActionPrint(ref m) => &m.context,
+            AnyMachine::Finished(ref m) => &m.context,
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// Mutable access to the user defined context of the current state.
+    pub fn context_mut(&mut self) -> &mut C {
+        match *self {
+            AnyMachine::WaitStart(ref mut m) => &mut m.context,
+            AnyMachine::WaitInput(ref mut m) => &mut m.context,
+            AnyMachine::ActionLoad(ref mut m) => &mut m.context,
+            AnyMachine::ActionPrint(ref mut m) => &mut m.context,
+            AnyMachine::Finished(ref mut m) => &mut m.context,
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// Edges which can be applied onto the current state.
+    ///
+    /// Pullup edges are included, even though the stack storage could hold a
+    /// Transaction not matching their next state.
+    pub fn moves(&self) -> Vec<Edge> {
+        TRANSITIONS
+            .iter()
+            .cloned()
+            .filter(|e| e.from == self.state())
+            .collect()
+    }
+
+    /// Build a [`MachineError`] from some error, with the current machine as snapshot.
+    pub fn error<E: Fail>(&self, context: ErrorKind, error: E) -> MachineError {
+        match *self {
+            AnyMachine::WaitStart(ref m) => MachineError::new(error, context, m),
+            AnyMachine::WaitInput(ref m) => MachineError::new(error, context, m),
+            AnyMachine::ActionLoad(ref m) => MachineError::new(error, context, m),
+            AnyMachine::ActionPrint(ref m) => MachineError::new(error, context, m),
+            AnyMachine::Finished(ref m) => MachineError::new(error, context, m),
+            AnyMachine::Poisoned => poisoned(),
+        }
+    }
+
+    /// Apply the provided edge onto the current state.
+    ///
+    /// Transition and pushdown edges require a Transaction for the next state, pullup
+    /// edges restore it from storage and MUST NOT receive one. The installed middleware
+    /// is consulted around the edge, see [`Chain`].
+    ///
+    /// # Errors
+    /// The machine is left untouched when the edge does not start from the current state,
+    /// when the Transaction doesn't match the next state, when the guard of the context
+    /// or a layer of the middleware rejects the edge or when the transition itself fails.
+    ///
+    /// ```
+    /// use automaton_test::dynamic::AnyMachine;
+    /// use automaton_test::registry::{Edge, StateId};
+    /// use automaton_test::transaction::{Epsilon, PrintTransaction};
+    ///
+    /// let mut machine: AnyMachine = AnyMachine::default();
+    /// machine.apply(Edge::START_TO_INPUT, Some(Epsilon.into())).unwrap();
+    /// let empty = PrintTransaction(String::new());
+    /// assert!(machine.apply(Edge::INPUT_PUSHDOWN_PRINT, Some(empty.into())).is_err());
+    /// assert_eq!(machine.state(), StateId::WaitInput);
+    /// assert_eq!(machine.journal().events.len(), 1);
+    /// ```
+    pub fn apply(
+        &mut self,
+        edge: Edge,
+        transaction: Option<TransactionItem>,
+    ) -> Result<(), MachineError> {
+        let chain = self.middleware().clone();
+        let step = Step {
+            edge,
+            transaction,
+            actor: None,
+        };
+        chain.apply(self, step)
+    }
+
+    /// Apply the edge without consulting the middleware, see [`AnyMachine::apply`].
+    ///
+    /// A failing edge is reported once through the spans of the machine, see [`Spans`].
+    pub(crate) fn apply_unwrapped(
+        &mut self,
+        edge: Edge,
+        transaction: Option<TransactionItem>,
+    ) -> Result<(), MachineError> {
+        let result = self
+            .check_edge(edge, transaction)
+            .and_then(|transaction| step(self, edge, transaction));
+        if let Err(ref error) = result {
+            self.spans().failure(edge, error);
+        }
+        result
+    }
+
+    /// Verify the edge starts from the current state and the presence of the
+    /// Transaction matches its kind.
+    ///
+    /// The Transaction is returned for non-pullup edges.
+    fn check_edge(
+        &self,
+        edge: Edge,
+        transaction: Option<TransactionItem>,
+    ) -> Result<Option<TransactionItem>, MachineError> {
+        if edge.from != self.state() {
+            let expected = format!("edge starting from `{}`", self.state());
+            let error = RuntimeConstraintError::from((expected, edge));
+            return Err(self.error(ErrorKind::LogicError, error));
+        }
+
+        match (edge.kind, transaction) {
+            (EdgeKind::Pullup, None) => Ok(None),
+            (EdgeKind::Pullup, Some(t)) => {
+                let error = RuntimeConstraintError::from(("no transaction", t));
+                Err(self.error(ErrorKind::ConstraintError, error))
+            }
+            (_, Some(t)) => Ok(Some(t)),
+            (_, None) => {
+                let error = RuntimeConstraintError::from(("a transaction", "none"));
+                Err(self.error(ErrorKind::ConstraintError, error))
+            }
+        }
+    }
+
+    /// Apply the edge described by the provided command, see [`AnyMachine::apply`].
+    ///
+    /// The applied edge is returned.
+    pub fn execute(&mut self, command: Command) -> Result<Edge, MachineError> {
+        self.execute_as(command, None)
+    }
+
+    /// Apply the edge described by the command on behalf of the provided actor, see
+    /// [`AnyMachine::execute`].
+    ///
+    /// Commands which don't describe an edge are observed by the middleware as well.
+    pub(crate) fn execute_as(
+        &mut self,
+        command: Command,
+        actor: Option<Actor>,
+    ) -> Result<Edge, MachineError> {
+        let chain = self.middleware().clone();
+        let edge = match command.edge(self.state()) {
+            Ok(edge) => edge,
+            Err(e) => {
+                let error = self.error(ErrorKind::LogicError, e);
+                chain.unresolved(self, &command, actor.as_ref(), &error);
+                return Err(error);
+            }
+        };
+        let step = Step {
+            edge,
+            transaction: command.into_transaction(),
+            actor,
+        };
+        chain.apply(self, step)?;
+        Ok(edge)
+    }
+}
+
+/// Apply the edge onto the machine, see [`AnyMachine::apply`].
+///
+/// All checks run against the borrowed machine, which is only moved out once they passed.
+/// The transaction is guaranteed to be present for non-pullup edges.
+fn step<C>(
+    machine: &mut AnyMachine<C>,
+    edge: Edge,
+    transaction: Option<TransactionItem>,
+) -> Result<(), MachineError>
+where
+    C: Context,
+{
+    // Moves the machine out of the named variant. A panic while applying the edge leaves
+    // the machine poisoned.
+    macro_rules! take {
+        ($variant:ident) => {
+            match mem::replace(machine, AnyMachine::Poisoned) {
+                AnyMachine::$variant(m) => m,
+                _ => unreachable!("Matched above"),
+            }
+        };
+    }
+
+    // Unpacks the provided transaction.
+    macro_rules! unpack {
+        ($machine:ident) => {
+            unpack_transaction(transaction.expect("Checked above"))
+                .context(ErrorKind::ConstraintError, $machine)?
+        };
+    }
+
+    // Each arm MUST match an entry of `TRANSITIONS` !
+    let next = match (&*machine, edge) {
+        (&AnyMachine::WaitStart(ref m), Edge::START_TO_INPUT) => {
+            let t = unpack!(m);
+            let checked = Checked::transition::<_, Machine<_, C>>(m, &t)?;
+            AnyMachine::WaitInput(TransitionFrom::transition_from(take!(WaitStart), t, checked))
+        }
+        (&AnyMachine::WaitInput(ref m), Edge::INPUT_TO_FINISHED) => {
+            let t = unpack!(m);
+            let checked = Checked::transition::<_, Machine<_, C>>(m, &t)?;
+            AnyMachine::Finished(TransitionFrom::transition_from(take!(WaitInput), t, checked))
+        }
+        (&AnyMachine::WaitInput(ref m), Edge::INPUT_PUSHDOWN_PRINT) => {
+            let t = unpack!(m);
+            let checked = Checked::pushdown::<_, Machine<_, C>, TransactionItem>(m, &t)?;
+            AnyMachine::ActionPrint(PushdownFrom::pushdown_from(take!(WaitInput), t, checked))
+        }
+        (&AnyMachine::ActionPrint(ref m), Edge::PRINT_PULLUP_INPUT) => {
+            check_restore::<Epsilon, _>(m)?;
+            let checked = Checked::pullup::<_, Machine<_, C>, TransactionItem>(m)?;
+            let next = PullupFrom::pullup_from(take!(ActionPrint), checked);
+            AnyMachine::WaitInput(next.expect("Stored transaction checked above"))
+        }
+        (&AnyMachine::ActionPrint(ref m), Edge::PRINT_PUSHDOWN_LOAD) => {
+            let t = unpack!(m);
+            let checked = Checked::pushdown::<_, Machine<_, C>, TransactionItem>(m, &t)?;
+            AnyMachine::ActionLoad(PushdownFrom::pushdown_from(take!(ActionPrint), t, checked))
+        }
+        (&AnyMachine::ActionLoad(ref m), Edge::LOAD_PULLUP_PRINT) => {
+            check_restore::<PrintTransaction, _>(m)?;
+            let checked = Checked::pullup::<_, Machine<_, C>, TransactionItem>(m)?;
+            let next = PullupFrom::pullup_from(take!(ActionLoad), checked);
+            AnyMachine::ActionPrint(next.expect("Stored transaction checked above"))
+        }
+        (machine, _) => {
+            let error = RuntimeConstraintError::from(("an implemented edge", edge));
+            return Err(machine.error(ErrorKind::LogicError, error));
+        }
+    };
+    *machine = next;
+    Ok(())
+}
+
+/// Accessing a machine which panicked while applying an edge.
+fn poisoned() -> ! {
+    panic!("State machine poisoned by a panic while applying an edge")
+}
+
+/// Verify that a pullup from the provided machine restores a valid Transaction of type `T`,
+/// so the pullup can't fail once the machine was moved.
+fn check_restore<T, M>(machine: &M) -> Result<(), MachineError>
+where
+    T: Transaction + Validate + 'static,
+    TransactionItem: TryInto<T, Error = RuntimeConstraintError>,
+    M: StateContainer + ServiceCompliance<StackStorage<TransactionItem>>,
+    M: Clone + Debug + Send + Sync + 'static,
+{
+    let storage: &StackStorage<TransactionItem> = ServiceCompliance::get(machine);
+    let item = storage
+        .tape
+        .last()
+        .cloned()
+        .ok_or(StackPopError)
+        .context(ErrorKind::LogicError, machine)?;
+    unpack_transaction::<T, _>(item).context(ErrorKind::ConstraintError, machine)?;
+    Ok(())
+}
+
+/// Rebuild a state machine by re-applying each event of the provided journal, starting
+/// from [`StateId::INITIAL`].
+///
+/// The events are applied through the real transition implementations, so the rebuilt
+/// machine records an identical journal. The random number generator is restored to its
+/// recorded state before each event, so numbers drawn in between are generated again.
+/// The journal doesn't record the context, the rebuilt machine starts from its default.
+///
+/// # Errors
+/// The index of the first event which doesn't match the machine definition is reported.
+///
+/// ```
+/// use automaton_test::dynamic::{replay, AnyMachine};
+/// use automaton_test::registry::Edge;
+/// use automaton_test::service::Journal;
+/// use automaton_test::transaction::{Epsilon, PrintTransaction};
+/// use automaton_test::Machine;
+///
+/// let mut machine = AnyMachine::WaitStart(Machine::with_seed(7));
+/// machine.apply(Edge::START_TO_INPUT, Some(Epsilon.into())).unwrap();
+/// let roll = machine.rng_mut().next_below(6);
+/// let print = PrintTransaction(format!("Rolled {}", roll + 1));
+/// machine.apply(Edge::INPUT_PUSHDOWN_PRINT, Some(print.into())).unwrap();
+///
+/// // Store the journal as text and rebuild the machine from it.
+/// let stored = machine.journal().to_string();
+/// let journal: Journal = stored.parse().unwrap();
+/// let rebuilt: AnyMachine = replay(&journal).unwrap();
+/// assert_eq!(rebuilt.state(), machine.state());
+/// assert_eq!(rebuilt.journal(), machine.journal());
+/// assert_eq!(rebuilt.rng(), machine.rng());
+///
+/// // A journal which skips the first transition no longer matches.
+/// let broken: Journal = stored.lines().skip(1).collect::<Vec<_>>().join("\n").parse().unwrap();
+/// assert_eq!(replay::<()>(&broken).unwrap_err().index, 0);
+/// ```
+pub fn replay<C>(journal: &Journal) -> Result<AnyMachine<C>, ReplayError>
+where
+    C: Context + Default,
+{
+    let mut machine = AnyMachine::default();
+    for (index, event) in journal.events.iter().enumerate() {
+        let transaction = match event.transaction {
+            Some(ref t) => Some(
+                t.parse()
+                    .map_err(|e| machine.error(ErrorKind::ConstraintError, e))
+                    .map_err(|inner| ReplayError { index, inner })?,
+            ),
+            None => None,
+        };
+        if let Some(rng) = event.rng {
+            *machine.rng_mut() = rng;
+        }
+        machine
+            .apply(event.edge, transaction)
+            .map_err(|inner| ReplayError { index, inner })?;
+    }
+    Ok(machine)
+}
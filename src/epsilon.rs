@@ -0,0 +1,111 @@
+//! Automatic following of [`Epsilon`] edges.
+//!
+//! Like the zero-step transitions of an NFA, an edge which only requires an Epsilon
+//! Transaction carries no information. When such an edge is the only move out of a
+//! state, the machine can follow it without any input. Following repeats until a state
+//! is reached which offers a choice, requires a real Transaction or is terminal.
+//!
+//! ```
+//! use automaton_test::dynamic::AnyMachine;
+//! use automaton_test::registry::{Edge, StateId};
+//!
+//! let mut machine: AnyMachine = AnyMachine::default();
+//! // Wait<Start> can only transition into Wait<Input>, which offers a choice.
+//! assert_eq!(machine.follow_epsilon().unwrap(), vec![Edge::START_TO_INPUT]);
+//! assert_eq!(machine.state(), StateId::WaitInput);
+//! assert!(machine.follow_epsilon().unwrap().is_empty());
+//! ```
+//!
+//! [`Epsilon`]: ::transaction::Epsilon
+
+use command::Command;
+use context::Context;
+use dynamic::AnyMachine;
+use function::error::{ErrorKind, MachineError, RuntimeConstraintError};
+use registry::{Edge, EdgeKind, StateId};
+use transaction::Epsilon;
+use TRANSITIONS;
+
+/// The edge of the provided graph which can be followed automatically out of the
+/// provided state, if any.
+///
+/// Pullup edges are never followed, since they restore a Transaction from storage.
+pub fn epsilon_edge(edges: &[Edge], from: StateId) -> Option<Edge> {
+    let mut moves = edges.iter().filter(|e| e.from == from);
+    match (moves.next(), moves.next()) {
+        (Some(&edge), None) if edge.kind != EdgeKind::Pullup && edge.to.takes_epsilon() => {
+            Some(edge)
+        }
+        _ => None,
+    }
+}
+
+/// All edges of the provided graph which are followed automatically, in order, starting
+/// from the provided state.
+///
+/// # Errors
+/// Following could go on forever when the edges form a cycle.
+///
+/// ```
+/// use automaton_test::epsilon::epsilon_chain;
+/// use automaton_test::registry::{Edge, EdgeKind, StateId};
+///
+/// let back = Edge {
+///     kind: EdgeKind::Transition,
+///     from: StateId::WaitInput,
+///     to: StateId::WaitStart,
+/// };
+/// let edges = [Edge::START_TO_INPUT, back];
+/// assert!(epsilon_chain(&edges, StateId::WaitStart).is_err());
+/// assert!(epsilon_chain(&edges[..1], StateId::WaitStart).is_ok());
+/// ```
+pub fn epsilon_chain(edges: &[Edge], from: StateId) -> Result<Vec<Edge>, RuntimeConstraintError> {
+    let mut chain: Vec<Edge> = vec![];
+    let mut current = from;
+    while let Some(edge) = epsilon_edge(edges, current) {
+        if edge.to == from || chain.iter().any(|e| e.to == edge.to) {
+            let path = chain
+                .iter()
+                .chain(Some(&edge))
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(("a finite chain of epsilon edges", format!("cycle {}", path)).into());
+        }
+        chain.push(edge);
+        current = edge.to;
+    }
+    Ok(chain)
+}
+
+impl<C> AnyMachine<C>
+where
+    C: Context,
+{
+    /// Follow all epsilon edges of [`TRANSITIONS`] starting from the current state, see
+    /// [`epsilon_chain`].
+    ///
+    /// The followed edges are returned in order.
+    ///
+    /// # Errors
+    /// The machine is left untouched when the edges form a cycle. A failing transition
+    /// is returned, the machine is left in the state it was followed to.
+    pub fn follow_epsilon(&mut self) -> Result<Vec<Edge>, MachineError> {
+        let chain = epsilon_chain(TRANSITIONS, self.state())
+            .map_err(|e| self.error(ErrorKind::LogicError, e))?;
+        for &edge in &chain {
+            self.apply(edge, Some(Epsilon.into()))?;
+        }
+        Ok(chain)
+    }
+
+    /// Execute the command, see [`AnyMachine::execute`], and follow all epsilon edges
+    /// afterwards, see [`AnyMachine::follow_epsilon`].
+    ///
+    /// The edge of the command is returned, together with the followed edges.
+    pub fn execute_closed(&mut self, command: Command) -> Result<(Edge, Vec<Edge>), MachineError> {
+        let edge = self.execute(command)?;
+        let followed = self.follow_epsilon()?;
+        Ok((edge, followed))
+    }
+}
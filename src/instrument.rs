@@ -0,0 +1,116 @@
+//! Integration with the `tracing` ecosystem, enabled through the `tracing` feature.
+//!
+//! Each applied edge emits an event holding the edge, its kind and the Transaction of
+//! the next state. A pushdown also opens a span, which is closed by the matching pullup,
+//! so the events of nested actions show up within the span of their pushdown. An edge
+//! which fails to apply onto the dynamic machine emits a warning holding the edge and
+//! the kind of [`MachineError`].
+//!
+//! Without the feature, no events are emitted and [`Spans`] holds nothing.
+
+use std::fmt::Display;
+
+#[cfg(feature = "tracing")]
+use tracing::{self, Span};
+
+use function::error::MachineError;
+use marker::Service;
+use registry::Edge;
+
+/// Service holding the spans opened by pushdowns, the innermost last.
+///
+/// Clones hold no spans, so checkpoints and the snapshot within each error don't keep
+/// spans open. Events of a clone are emitted within the span current to the caller.
+#[derive(Debug, Default)]
+pub struct Spans {
+    #[cfg(feature = "tracing")]
+    stack: Vec<Span>,
+}
+
+impl Clone for Spans {
+    fn clone(&self) -> Self {
+        Spans::default()
+    }
+}
+
+impl Service for Spans {}
+
+impl Spans {
+    /// Amount of open spans, which is always zero without the `tracing` feature.
+    pub fn depth(&self) -> usize {
+        #[cfg(feature = "tracing")]
+        return self.stack.len();
+        #[cfg(not(feature = "tracing"))]
+        return 0;
+    }
+
+    /// Emit the event of an applied transition.
+    pub fn transition<T: Display>(&mut self, edge: Edge, transaction: &T) {
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            parent: &self.parent(),
+            edge = %edge,
+            kind = ?edge.kind,
+            transaction = %transaction,
+            "transition"
+        );
+    }
+
+    /// Emit the event of an applied pushdown and open its span.
+    pub fn pushdown<T: Display>(&mut self, edge: Edge, transaction: &T) {
+        #[cfg(feature = "tracing")]
+        {
+            let parent = self.parent();
+            tracing::info!(
+                parent: &parent,
+                edge = %edge,
+                kind = ?edge.kind,
+                transaction = %transaction,
+                "pushdown"
+            );
+            let span = tracing::info_span!(
+                parent: &parent,
+                "pushdown",
+                state = %edge.to,
+                transaction = %transaction
+            );
+            self.stack.push(span);
+        }
+    }
+
+    /// Emit the event of an applied pullup and close the span of the matching pushdown.
+    ///
+    /// The provided Transaction is the one restored for the next state.
+    pub fn pullup<T: Display>(&mut self, edge: Edge, transaction: &T) {
+        #[cfg(feature = "tracing")]
+        {
+            // The event is part of the span it leaves, which closes when dropped.
+            let span = self.stack.pop().unwrap_or_else(Span::current);
+            tracing::info!(
+                parent: &span,
+                edge = %edge,
+                kind = ?edge.kind,
+                transaction = %transaction,
+                "pullup"
+            );
+        }
+    }
+
+    /// Emit the warning of an edge which failed to apply.
+    pub fn failure(&self, edge: Edge, error: &MachineError) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            parent: &self.parent(),
+            edge = %edge,
+            kind = ?error.kind(),
+            error = %error,
+            "machine error"
+        );
+    }
+
+    /// Span of the innermost pushdown, or the span current to the caller.
+    #[cfg(feature = "tracing")]
+    fn parent(&self) -> Span {
+        self.stack.last().cloned().unwrap_or_else(Span::current)
+    }
+}
@@ -24,6 +24,14 @@
 
 #[macro_use]
 extern crate failure;
+extern crate roxmltree;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
+#[cfg(feature = "tracing")]
+extern crate tracing;
 
 pub mod function {
     //! Contains the core functionality items for our system.
@@ -44,6 +52,26 @@ pub mod function {
         type Transaction;
     }
 
+    /// Trait for verifying the contents of a Transaction before it's used to enter
+    /// a state.
+    ///
+    /// Validation happens automatically when transitioning, pushing down and when
+    /// restoring a Transaction during pullup.
+    pub trait Validate {
+        /// Verify that the held values are acceptable.
+        ///
+        /// The default implementation accepts every value.
+        fn validate(&self) -> Result<(), error::RuntimeConstraintError> {
+            Ok(())
+        }
+    }
+
+    /// Trait describing the fields of a Transaction, so its shape is known at runtime.
+    pub trait Fields {
+        /// Names of the held fields, in order of declaration.
+        const FIELDS: &'static [&'static str];
+    }
+
     /// Trait for implementing a certain service on the state machine.
     ///
     /// Because of this design exactly one object of each service type can be hooked onto
@@ -77,10 +105,38 @@ pub mod function {
         /// the error occurred.
         #[derive(Debug)]
         pub struct MachineError {
-            machine: Box<(Debug + Send + Sync)>,
+            machine: Box<dyn Debug + Send + Sync>,
             inner: Context<ErrorKind>,
         }
 
+        impl MachineError {
+            /// Builds a [`MachineError`] from some error.
+            ///
+            /// See [`SnapshottedErrorExt::context`] for the meaning of each parameter.
+            pub fn new<E, M>(error: E, context: ErrorKind, machine: &M) -> Self
+            where
+                E: Fail,
+                M: StateContainer + Clone + Debug + Sync + Send + 'static,
+            {
+                MachineError {
+                    machine: Box::new(machine.clone()),
+                    // Build new context for our own error kind.
+                    // and chain the previous one..
+                    inner: error.context(context),
+                }
+            }
+
+            /// The category of this error.
+            pub fn kind(&self) -> ErrorKind {
+                *self.inner.get_context()
+            }
+
+            /// Snapshot of the state machine at the moment the error occurred.
+            pub fn snapshot(&self) -> &(dyn Debug + Send + Sync) {
+                &*self.machine
+            }
+        }
+
         impl Fail for MachineError {
             fn cause(&self) -> Option<&Fail> {
                 self.inner.cause()
@@ -107,6 +163,9 @@ pub mod function {
             /// his code.
             #[fail(display = "A logical error ocurred")]
             LogicError,
+            /// Error indicating the acting party is not allowed to perform the operation.
+            #[fail(display = "An operation was not permitted")]
+            PermissionError,
         }
 
         /// Trait facilitating error creation with a snapshot of the state machine
@@ -137,15 +196,8 @@ pub mod function {
             where
                 M: StateContainer + Clone + Debug + Sync + Send + 'static,
             {
-                self.map_err(move |failure| {
-                    // Build and return custom error type
-                    MachineError {
-                        machine: Box::new(machine.clone()),
-                        // Build new context for our own error kind.
-                        // and chain the previous one..
-                        inner: failure.context(context),
-                    }
-                })
+                // Build and return custom error type
+                self.map_err(move |failure| MachineError::new(failure, context, machine))
             }
         }
 
@@ -181,6 +233,8 @@ pub mod function {
         //! Expect to find small utilities here, but they are mostly used by the hidden parts of the core.
         use std::convert::TryInto;
 
+        use super::Validate;
+        use super::error::RuntimeConstraintError;
         use marker::{Transaction, TransactionContainer};
 
         /* Transaction helpers */
@@ -196,12 +250,63 @@ pub mod function {
         /// Unpack a wrapped transaction into an owned value.
         ///
         /// It's of course necessary to
-        pub fn unpack_transaction<T, TC>(tc: TC) -> Result<T, TC::Error>
+        ///
+        /// The unpacked transaction is validated before it's returned.
+        pub fn unpack_transaction<T, TC>(tc: TC) -> Result<T, RuntimeConstraintError>
         where
-            T: Transaction + 'static,
-            TC: TransactionContainer + TryInto<T> + 'static,
+            T: Transaction + Validate + 'static,
+            TC: TransactionContainer + TryInto<T, Error = RuntimeConstraintError> + 'static,
         {
-            tc.try_into()
+            let t = tc.try_into()?;
+            t.validate()?;
+            Ok(t)
+        }
+
+        /* Text helpers */
+        /// Wrap the provided text in double quotes, escaping characters where necessary.
+        ///
+        /// The result never contains a newline, so it can safely be stored line by line.
+        pub fn quote(text: &str) -> String {
+            let mut quoted = String::with_capacity(text.len() + 2);
+            quoted.push('"');
+            for c in text.chars() {
+                match c {
+                    '"' => quoted.push_str("\\\""),
+                    '\\' => quoted.push_str("\\\\"),
+                    '\n' => quoted.push_str("\\n"),
+                    '\r' => quoted.push_str("\\r"),
+                    '\t' => quoted.push_str("\\t"),
+                    c => quoted.push(c),
+                }
+            }
+            quoted.push('"');
+            quoted
+        }
+
+        /// Reverse operation of [`quote`].
+        pub fn unquote(quoted: &str) -> Result<String, RuntimeConstraintError> {
+            let malformed = || RuntimeConstraintError::from(("quoted text", quoted));
+            if quoted.len() < 2 || !quoted.starts_with('"') || !quoted.ends_with('"') {
+                return Err(malformed());
+            }
+
+            let mut text = String::with_capacity(quoted.len());
+            let mut chars = quoted[1..quoted.len() - 1].chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => match chars.next() {
+                        Some('"') => text.push('"'),
+                        Some('\\') => text.push('\\'),
+                        Some('n') => text.push('\n'),
+                        Some('r') => text.push('\r'),
+                        Some('t') => text.push('\t'),
+                        _ => return Err(malformed()),
+                    },
+                    '"' => return Err(malformed()),
+                    c => text.push(c),
+                }
+            }
+            Ok(text)
         }
     }
 }
@@ -238,54 +343,149 @@ pub mod marker {
     ///
     /// The semantics are limited to the set of action events a user can generate.
     pub trait ActionableMarker {}
+    /// (State) Types which are allowed to have outgoing transitions.
+    ///
+    /// Each state which doesn't end the execution of the state machine MUST implement this
+    /// trait explicitly. The compiler rejects every transition, pushdown and pullup out of
+    /// the other, terminal, states.
+    pub trait NonTerminalState {}
 }
 
 pub mod stm {
     //! Traits enforcing state machine behaviour.
 
-    use function::{ServiceCompliance, State, StateContainer, error::MachineError};
-    use marker::{Transaction, TransactionContainer};
+    use std::fmt::Debug;
+
+    use function::error::{ErrorKind, MachineError, SnapshottedErrorExt};
+    use function::{ServiceCompliance, State, StateContainer, Validate};
+    use marker::{NonTerminalState, Transaction, TransactionContainer};
     use service::StackStorage;
 
+    /// Proof that the checks preceding a transition have passed.
+    ///
+    /// The `*From` traits require this value, which can only be built by this crate.
+    /// Their methods can therefore be implemented anywhere, but only be called through
+    /// [`TransitionInto`], [`PushdownInto`] and [`PullupInto`]. Those validate the
+    /// Transaction and check the guard first, so no transition skips them.
+    #[derive(Debug)]
+    pub struct Checked(());
+
+    impl Checked {
+        /// Validate the Transaction and check the guard of a transition from `old`.
+        ///
+        /// Only borrows the state machine, so callers can keep it when a check fails.
+        pub(crate) fn transition<S, T>(
+            old: &S,
+            t: &<T::State as State>::Transaction,
+        ) -> Result<Self, MachineError>
+        where
+            S: StateContainer + Clone + Debug + Send + Sync + 'static,
+            S::State: NonTerminalState,
+            T: TransitionFrom<S> + StateContainer,
+            T::State: State + 'static,
+            <T::State as State>::Transaction: Transaction + Validate + 'static,
+        {
+            t.validate().context(ErrorKind::ConstraintError, old)?;
+            T::guard(old, t)?;
+            Ok(Checked(()))
+        }
+
+        /// Validate the Transaction and check the guard of a pushdown from `old`.
+        ///
+        /// Only borrows the state machine, so callers can keep it when a check fails.
+        pub(crate) fn pushdown<S, T, TTC>(
+            old: &S,
+            t: &<T::State as State>::Transaction,
+        ) -> Result<Self, MachineError>
+        where
+            S: StateContainer + Clone + Debug + Send + Sync + 'static,
+            S::State: NonTerminalState,
+            TTC: TransactionContainer + 'static,
+            T: PushdownFrom<S, TTC> + StateContainer + 'static,
+            T::State: State + 'static,
+            <T::State as State>::Transaction: Transaction + Validate + 'static,
+        {
+            t.validate().context(ErrorKind::ConstraintError, old)?;
+            T::guard(old, t)?;
+            Ok(Checked(()))
+        }
+
+        /// Check the guard of a pullup from `old`.
+        ///
+        /// Only borrows the state machine, so callers can keep it when a check fails.
+        pub(crate) fn pullup<S, T, TTC>(old: &S) -> Result<Self, MachineError>
+        where
+            S: StateContainer + ServiceCompliance<StackStorage<TTC>> + 'static,
+            S::State: NonTerminalState,
+            TTC: TransactionContainer + 'static,
+            T: PullupFrom<S, TTC> + StateContainer + 'static,
+            T::State: State + 'static,
+            <T::State as State>::Transaction: Transaction + 'static,
+        {
+            T::guard(old)?;
+            Ok(Checked(()))
+        }
+    }
+
     /// Types, state machines residing in a certain state, which transform one-sided
     /// into a next Type.
     ///
     /// A state machine is said to transition from A into B when the current state is A,
     /// a Transaction object for state B is provided and the following transition is
     /// valid [A -> B].
+    ///
+    /// The state of `T` MUST be a [`NonTerminalState`](::marker::NonTerminalState).
     pub trait TransitionFrom<T>
     where
         T: StateContainer + 'static,
+        T::State: NonTerminalState,
         Self: StateContainer + 'static,
         Self::State: State + 'static,
-        <Self::State as State>::Transaction: Transaction + Copy + 'static,
+        <Self::State as State>::Transaction: Transaction + 'static,
     {
         /// Transition from the provided state into the implementing state.
-        fn transition_from(_: T, _: <Self::State as State>::Transaction) -> Self;
+        ///
+        /// Use [`TransitionInto::transition`] to call this method, see [`Checked`].
+        fn transition_from(_: T, _: <Self::State as State>::Transaction, _: Checked) -> Self;
+
+        /// Verify that the transition from the provided state may happen.
+        ///
+        /// The default implementation accepts every transition.
+        fn guard(_: &T, _: &<Self::State as State>::Transaction) -> Result<(), MachineError> {
+            Ok(())
+        }
     }
 
     /// Syntax simplifying trait in accordance to [`TransitionFrom`].
     pub trait TransitionInto<T>
     where
         T: StateContainer + 'static,
-        Self: StateContainer + 'static,
+        Self: StateContainer + Clone + Debug + Send + Sync + 'static,
+        Self::State: NonTerminalState,
         T::State: State + 'static,
-        <T::State as State>::Transaction: Transaction + Copy + 'static,
+        <T::State as State>::Transaction: Transaction + Validate + 'static,
     {
         /// Transition from Self into the desired state.
-        fn transition(self, _: <T::State as State>::Transaction) -> T;
+        ///
+        /// # Errors
+        /// The provided Transaction is validated before the transition happens, see
+        /// [`Validate`]. Afterwards the guard of the next state is checked, see
+        /// [`TransitionFrom::guard`].
+        fn transition(self, _: <T::State as State>::Transaction) -> Result<T, MachineError>;
     }
 
     impl<T, S> TransitionInto<T> for S
     where
-        S: StateContainer + 'static,
+        S: StateContainer + Clone + Debug + Send + Sync + 'static,
+        S::State: NonTerminalState,
         T: TransitionFrom<S> + StateContainer,
         T::State: State + 'static,
-        <T::State as State>::Transaction: Transaction + Copy + 'static,
+        <T::State as State>::Transaction: Transaction + Validate + 'static,
     {
-        fn transition(self, t: <T::State as State>::Transaction) -> T {
+        fn transition(self, t: <T::State as State>::Transaction) -> Result<T, MachineError> {
+            let checked = Checked::transition::<S, T>(&self, &t)?;
             // self is of type S.
-            T::transition_from(self, t)
+            Ok(T::transition_from(self, t, checked))
         }
     }
 
@@ -300,16 +500,28 @@ pub mod stm {
     /// A state machine is said to pushdown from A into B when the current state is A,
     /// a Transaction object for state B is provided and the following transition is
     /// valid [A -> B].
+    ///
+    /// The state of `T` MUST be a [`NonTerminalState`](::marker::NonTerminalState).
     pub trait PushdownFrom<T, TTC>
     where
         TTC: TransactionContainer + 'static,
         T: StateContainer + 'static,
+        T::State: NonTerminalState,
         Self: StateContainer + ServiceCompliance<StackStorage<TTC>> + 'static,
         Self::State: State + 'static,
-        <Self::State as State>::Transaction: Transaction + Copy + 'static,
+        <Self::State as State>::Transaction: Transaction + 'static,
     {
         /// Transition from the provided state into the implementing state.
-        fn pushdown_from(_: T, _: <Self::State as State>::Transaction) -> Self;
+        ///
+        /// Use [`PushdownInto::pushdown`] to call this method, see [`Checked`].
+        fn pushdown_from(_: T, _: <Self::State as State>::Transaction, _: Checked) -> Self;
+
+        /// Verify that the pushdown from the provided state may happen.
+        ///
+        /// The default implementation accepts every pushdown.
+        fn guard(_: &T, _: &<Self::State as State>::Transaction) -> Result<(), MachineError> {
+            Ok(())
+        }
     }
 
     /// Syntax simplifying trait in accordance to [`PushdownFrom`].
@@ -318,24 +530,32 @@ pub mod stm {
         TTC: TransactionContainer + 'static,
         T: StateContainer + 'static,
         T::State: State + 'static,
-        <T::State as State>::Transaction: Transaction + Copy + 'static,
-        Self: StateContainer + 'static,
+        <T::State as State>::Transaction: Transaction + Validate + 'static,
+        Self: StateContainer + Clone + Debug + Send + Sync + 'static,
+        Self::State: NonTerminalState,
     {
         /// Transition from Self into the desired state.
-        fn pushdown(self, _: <T::State as State>::Transaction) -> T;
+        ///
+        /// # Errors
+        /// The provided Transaction is validated before the transition happens, see
+        /// [`Validate`]. Afterwards the guard of the next state is checked, see
+        /// [`PushdownFrom::guard`].
+        fn pushdown(self, _: <T::State as State>::Transaction) -> Result<T, MachineError>;
     }
 
     impl<T, TTC, S> PushdownInto<T, TTC> for S
     where
-        S: StateContainer + 'static,
+        S: StateContainer + Clone + Debug + Send + Sync + 'static,
+        S::State: NonTerminalState,
         TTC: TransactionContainer + 'static,
         T: PushdownFrom<S, TTC> + StateContainer + 'static,
         T::State: State + 'static,
-        <T::State as State>::Transaction: Transaction + Copy + 'static,
+        <T::State as State>::Transaction: Transaction + Validate + 'static,
     {
-        fn pushdown(self, t: <T::State as State>::Transaction) -> T {
+        fn pushdown(self, t: <T::State as State>::Transaction) -> Result<T, MachineError> {
+            let checked = Checked::pushdown::<S, T, TTC>(&self, &t)?;
             // self is of type S.
-            T::pushdown_from(self, t)
+            Ok(T::pushdown_from(self, t, checked))
         }
     }
 
@@ -350,10 +570,13 @@ pub mod stm {
     ///
     /// A state machine is said to pullup from B into A when the current state is B
     /// and the following transition is valid [A <- B].
+    ///
+    /// The state of `T` MUST be a [`NonTerminalState`](::marker::NonTerminalState).
     pub trait PullupFrom<T, TTC>
     where
         TTC: TransactionContainer + 'static,
         T: StateContainer + ServiceCompliance<StackStorage<TTC>> + 'static,
+        T::State: NonTerminalState,
         Self: StateContainer + Sized + 'static,
         Self::State: State + 'static,
         <Self::State as State>::Transaction: Transaction + 'static,
@@ -364,7 +587,16 @@ pub mod stm {
         /// There is a check at runtime which prevents a Pullup transition if it doesn't match
         /// the correct PushDown transition in a First In, Last Out (FILO) manner.
         /// Note: This part CANNOT be statically verified as far as I know?
-        fn pullup_from(_: T) -> Result<Self, MachineError>;
+        ///
+        /// Use [`PullupInto::pullup`] to call this method, see [`Checked`].
+        fn pullup_from(_: T, _: Checked) -> Result<Self, MachineError>;
+
+        /// Verify that the pullup from the provided state may happen.
+        ///
+        /// The default implementation accepts every pullup.
+        fn guard(_: &T) -> Result<(), MachineError> {
+            Ok(())
+        }
     }
 
     /// Syntax sumplifying trait in accordance to [`PullupFrom`].
@@ -375,22 +607,29 @@ pub mod stm {
         T::State: State + 'static,
         <T::State as State>::Transaction: Transaction + 'static,
         Self: StateContainer + ServiceCompliance<StackStorage<TTC>> + Sized + 'static,
+        Self::State: NonTerminalState,
     {
         /// Transition from Self into the desired state.
+        ///
+        /// # Errors
+        /// The guard of the previous state is checked before the transition happens, see
+        /// [`PullupFrom::guard`].
         fn pullup(self) -> Result<T, MachineError>;
     }
 
     impl<T, TTC, S> PullupInto<T, TTC> for S
     where
         S: StateContainer + ServiceCompliance<StackStorage<TTC>> + 'static,
+        S::State: NonTerminalState,
         TTC: TransactionContainer + 'static,
         T: PullupFrom<S, TTC> + StateContainer + 'static,
         T::State: State + 'static,
-        <T::State as State>::Transaction: Transaction + Copy + 'static,
+        <T::State as State>::Transaction: Transaction + 'static,
     {
         fn pullup(self) -> Result<T, MachineError> {
+            let checked = Checked::pullup::<S, T, TTC>(&self)?;
             // self if of type S.
-            T::pullup_from(self)
+            T::pullup_from(self, checked)
         }
     }
 
@@ -399,8 +638,16 @@ pub mod stm {
 pub mod service {
     //! Types which attribute functionality to state machines.
 
+    use std::fmt::{self, Debug, Display, Formatter};
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
     use self::error::StackPopError;
+    use function::error::RuntimeConstraintError;
     use marker::{Service, TransactionContainer};
+    use registry::Edge;
 
     pub mod error {
         //! Types for simplifying error handling syntax.
@@ -446,13 +693,323 @@ pub mod service {
             self.tape.pop().ok_or(StackPopError)
         }
     }
+
+    /// One transition applied to a state machine.
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    pub struct Event {
+        /// The applied edge.
+        pub edge: Edge,
+        /// Textual representation of the provided Transaction.
+        ///
+        /// Pullup transitions restore their Transaction from storage, so there is
+        /// nothing to record for them.
+        pub transaction: Option<String>,
+        /// State of the random number generator when the edge was applied.
+        ///
+        /// Journals stored before the generator existed hold none.
+        pub rng: Option<Rng>,
+    }
+
+    impl Display for Event {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            write!(f, "{}", self.edge)?;
+            if let Some(ref rng) = self.rng {
+                write!(f, " @ {}", rng)?;
+            }
+            match self.transaction {
+                Some(ref t) => write!(f, " | {}", t),
+                None => Ok(()),
+            }
+        }
+    }
+
+    impl FromStr for Event {
+        type Err = RuntimeConstraintError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let (edge, transaction) = match s.find(" | ") {
+                Some(idx) => (&s[..idx], Some(s[idx + 3..].to_string())),
+                None => (s, None),
+            };
+            let (edge, rng) = match edge.find(" @ ") {
+                Some(idx) => (&edge[..idx], Some(edge[idx + 3..].parse()?)),
+                None => (edge, None),
+            };
+            Ok(Event {
+                edge: edge.parse()?,
+                transaction,
+                rng,
+            })
+        }
+    }
+
+    /// Append-only record of all transitions applied to a state machine.
+    ///
+    /// The textual representation, through [`Display`] and [`FromStr`], holds one
+    /// [`Event`] per line.
+    #[derive(Debug, Clone, Default, Eq, PartialEq)]
+    pub struct Journal {
+        /// Recorded events in order of application.
+        pub events: Vec<Event>,
+    }
+
+    impl Service for Journal {}
+
+    impl Journal {
+        /// Append the provided edge, Transaction and generator state to the journal.
+        pub fn record(&mut self, edge: Edge, transaction: Option<String>, rng: Option<Rng>) {
+            self.events.push(Event {
+                edge,
+                transaction,
+                rng,
+            });
+        }
+    }
+
+    impl Display for Journal {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            for event in &self.events {
+                writeln!(f, "{}", event)?;
+            }
+            Ok(())
+        }
+    }
+
+    impl FromStr for Journal {
+        type Err = RuntimeConstraintError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let events = s
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(str::parse)
+                .collect::<Result<_, _>>()?;
+            Ok(Journal { events })
+        }
+    }
+
+    /// Types which provide the current time to state machines.
+    ///
+    /// Time is expressed as the duration since some fixed origin, chosen by the
+    /// implementation.
+    pub trait Clock: Service {
+        /// The current time.
+        fn now(&self) -> Duration;
+
+        /// Block until the provided duration has passed.
+        fn sleep(&self, duration: Duration);
+    }
+
+    /// Clock following the time of the operating system.
+    ///
+    /// The origin is the moment the clock was built.
+    #[derive(Debug, Clone)]
+    pub struct SystemClock {
+        origin: Instant,
+    }
+
+    impl Default for SystemClock {
+        fn default() -> Self {
+            SystemClock {
+                origin: Instant::now(),
+            }
+        }
+    }
+
+    impl Service for SystemClock {}
+
+    impl Clock for SystemClock {
+        fn now(&self) -> Duration {
+            self.origin.elapsed()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            thread::sleep(duration)
+        }
+    }
+
+    /// Clock which only moves when told to, intended for deterministic tests.
+    ///
+    /// Clones share the same time, so one clone can be handed to the state machine while
+    /// another is used to advance time.
+    #[derive(Debug, Clone, Default)]
+    pub struct ManualClock {
+        now: Arc<Mutex<Duration>>,
+    }
+
+    impl Service for ManualClock {}
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Duration {
+            *self.now.lock().expect("Clock lock poisoned")
+        }
+
+        /// Advances the time immediately, without blocking.
+        fn sleep(&self, duration: Duration) {
+            self.advance(duration)
+        }
+    }
+
+    impl ManualClock {
+        /// Move the time forward by the provided duration.
+        pub fn advance(&self, by: Duration) {
+            *self.now.lock().expect("Clock lock poisoned") += by;
+        }
+
+        /// Move the time to the provided moment.
+        pub fn set(&self, now: Duration) {
+            *self.now.lock().expect("Clock lock poisoned") = now;
+        }
+    }
+
+    /// Service timestamping the moment the current state was entered.
+    ///
+    /// Every transition restarts the stopwatch, so the time spent within a state is known
+    /// without observing the state machine in between.
+    #[derive(Clone)]
+    pub struct Stopwatch {
+        clock: Arc<dyn Clock + Send + Sync>,
+        entered: Duration,
+    }
+
+    impl Default for Stopwatch {
+        fn default() -> Self {
+            Self::new(SystemClock::default())
+        }
+    }
+
+    impl Debug for Stopwatch {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            f.debug_struct("Stopwatch")
+                .field("entered", &self.entered)
+                .finish()
+        }
+    }
+
+    impl Service for Stopwatch {}
+
+    impl Stopwatch {
+        /// Build a stopwatch driven by the provided clock, started now.
+        pub fn new<K>(clock: K) -> Self
+        where
+            K: Clock + Send + Sync + 'static,
+        {
+            let entered = clock.now();
+            Stopwatch {
+                clock: Arc::new(clock),
+                entered,
+            }
+        }
+
+        /// Mark the current time as the moment the current state was entered.
+        pub fn restart(&mut self) {
+            self.entered = self.clock.now();
+        }
+
+        /// The moment the current state was entered.
+        pub fn entered(&self) -> Duration {
+            self.entered
+        }
+
+        /// Time spent within the current state.
+        ///
+        /// A clock which was set back before the moment of entering yields zero.
+        pub fn elapsed(&self) -> Duration {
+            self.clock
+                .now()
+                .checked_sub(self.entered)
+                .unwrap_or_default()
+        }
+    }
+
+    /// Deterministic random number generator, intended for replayable game logic.
+    ///
+    /// Each number is derived from the seed and the position within the stream only
+    /// (SplitMix64), so storing both is enough to continue the exact same stream.
+    /// The textual representation, through [`Display`] and [`FromStr`], is
+    /// `<seed>:<position>`.
+    ///
+    /// ```
+    /// use automaton_test::service::Rng;
+    ///
+    /// let mut rng = Rng::new(42);
+    /// let first = rng.next_u64();
+    /// let stored = rng.to_string();
+    ///
+    /// let mut restored: Rng = stored.parse().unwrap();
+    /// assert_eq!(restored.next_u64(), rng.next_u64());
+    /// assert_eq!(Rng::new(42).next_u64(), first);
+    /// ```
+    #[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+    pub struct Rng {
+        seed: u64,
+        position: u64,
+    }
+
+    impl Service for Rng {}
+
+    impl Rng {
+        /// Build a generator at the start of the stream of the provided seed.
+        pub fn new(seed: u64) -> Self {
+            Rng { seed, position: 0 }
+        }
+
+        /// The seed of the stream.
+        pub fn seed(&self) -> u64 {
+            self.seed
+        }
+
+        /// Amount of numbers generated from the stream so far.
+        pub fn position(&self) -> u64 {
+            self.position
+        }
+
+        /// Generate the next number of the stream.
+        pub fn next_u64(&mut self) -> u64 {
+            self.position = self.position.wrapping_add(1);
+            let mut z = self
+                .seed
+                .wrapping_add(self.position.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        }
+
+        /// Generate the next number of the stream, below the provided bound.
+        ///
+        /// # Panics
+        /// The bound MUST NOT be zero.
+        pub fn next_below(&mut self, bound: u64) -> u64 {
+            assert!(bound > 0, "Empty range");
+            ((u128::from(self.next_u64()) * u128::from(bound)) >> 64) as u64
+        }
+    }
+
+    impl Display for Rng {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            write!(f, "{}:{}", self.seed, self.position)
+        }
+    }
+
+    impl FromStr for Rng {
+        type Err = RuntimeConstraintError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let malformed = || RuntimeConstraintError::from(("<seed>:<position>", s));
+            let idx = s.find(':').ok_or_else(malformed)?;
+            Ok(Rng {
+                seed: s[..idx].trim().parse().map_err(|_| malformed())?,
+                position: s[idx + 1..].trim().parse().map_err(|_| malformed())?,
+            })
+        }
+    }
 }
 
 pub mod state {
     //! Types which encode the states to be used by a state machine.
 
     use function::State;
-    use marker::{ActionableMarker, TopLevelMarker, WaitableMarker};
+    use marker::{ActionableMarker, NonTerminalState, TopLevelMarker, WaitableMarker};
     use transaction::{Epsilon, PrintTransaction};
 
     ///////////////////
@@ -475,6 +1032,12 @@ pub mod state {
     {
     }
 
+    impl<W> NonTerminalState for Wait<W>
+    where
+        W: WaitableMarker,
+    {
+    }
+
     /// Wait condition state until the game has been started.
     #[derive(Debug, Clone)]
     pub struct Start();
@@ -513,6 +1076,12 @@ pub mod state {
     {
     }
 
+    impl<A> NonTerminalState for Action<A>
+    where
+        A: ActionableMarker,
+    {
+    }
+
     /// Action condition state indicating loading is in progress.
     #[derive(Debug, Clone)]
     pub struct Load();
@@ -553,12 +1122,19 @@ pub mod transaction {
     //! Types used to convey transition related information.
 
     use std::convert::TryFrom;
+    use std::fmt::{self, Display, Formatter};
+    use std::str::FromStr;
 
+    use function::{Fields, Validate};
     use function::error::RuntimeConstraintError;
+    use function::helper::{quote, unquote};
     use marker::{Transaction, TransactionContainer};
 
     /// Collection of known Transaction structures wrapped into a Sized
     /// item.
+    ///
+    /// The textual representation, through [`Display`] and [`FromStr`], is used
+    /// to store transactions outside of the running program.
     #[derive(Debug, Clone)]
     pub enum TransactionItem {
         /// See [`Epsilon`]
@@ -569,6 +1145,35 @@ pub mod transaction {
 
     impl TransactionContainer for TransactionItem {}
 
+    impl Display for TransactionItem {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            match *self {
+                TransactionItem::Epsilon(ref x) => Display::fmt(x, f),
+                TransactionItem::Print(ref x) => Display::fmt(x, f),
+            }
+        }
+    }
+
+    impl FromStr for TransactionItem {
+        type Err = RuntimeConstraintError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let s = s.trim();
+            let (name, value) = match s.find(' ') {
+                Some(idx) => (&s[..idx], s[idx..].trim()),
+                None => (s, ""),
+            };
+            match (name, value) {
+                ("Epsilon", "") => Ok(Epsilon.into()),
+                ("Print", value) => Ok(PrintTransaction(unquote(value)?).into()),
+                _ => {
+                    let expected = "`Epsilon` or `Print \"<text>\"`";
+                    Err((expected, s).into())
+                }
+            }
+        }
+    }
+
     /// Empty Transaction object.
     ///
     /// The name Epsilon is derived from NFA's where they indicate zero-step transitions
@@ -579,6 +1184,18 @@ pub mod transaction {
     pub struct Epsilon;
     impl Transaction for Epsilon {}
 
+    impl Validate for Epsilon {}
+
+    impl Fields for Epsilon {
+        const FIELDS: &'static [&'static str] = &[];
+    }
+
+    impl Display for Epsilon {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            f.write_str("Epsilon")
+        }
+    }
+
     impl From<Epsilon> for TransactionItem {
         fn from(x: Epsilon) -> Self {
             TransactionItem::Epsilon(x)
@@ -604,10 +1221,33 @@ pub mod transaction {
     ///
     /// This state is pure exemplary, I don't know what else to tell you
     /// about it..
-    #[derive(Debug, Clone, Copy)]
-    pub struct PrintTransaction(pub &'static str);
+    ///
+    /// The text is owned, so it can be built at runtime.
+    #[derive(Debug, Clone)]
+    pub struct PrintTransaction(pub String);
     impl Transaction for PrintTransaction {}
 
+    impl Fields for PrintTransaction {
+        const FIELDS: &'static [&'static str] = &["text"];
+    }
+
+    impl Validate for PrintTransaction {
+        fn validate(&self) -> Result<(), RuntimeConstraintError> {
+            if self.0.is_empty() {
+                let expected = "PrintTransaction with non-empty text";
+                let factual = format!("{:?}", self);
+                return Err((expected, factual).into());
+            }
+            Ok(())
+        }
+    }
+
+    impl Display for PrintTransaction {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            write!(f, "Print {}", quote(&self.0))
+        }
+    }
+
     impl From<PrintTransaction> for TransactionItem {
         fn from(x: PrintTransaction) -> Self {
             TransactionItem::Print(x)
@@ -630,15 +1270,44 @@ pub mod transaction {
     }
 }
 
+pub mod acl;
+pub mod cancel;
+pub mod chrome;
+pub mod codegen;
+pub mod command;
+#[cfg(doctest)]
+pub mod compile_fail;
+pub mod context;
+pub mod driver;
+pub mod dynamic;
+pub mod epsilon;
+pub mod instrument;
+pub mod middleware;
+pub mod registry;
+pub mod retry;
+pub mod runtime;
+pub mod scenario;
+pub mod scxml;
+pub mod schedule;
+pub mod supervisor;
+pub mod timeout;
+pub mod trace;
+
+use std::fmt::Display;
 use std::marker::PhantomData;
 
+use context::Context;
 use function::error::{ErrorKind, MachineError, SnapshottedErrorExt};
 use function::helper::{pack_transaction, unpack_transaction};
 use function::{ServiceCompliance, State, StateContainer};
-use marker::TopLevelMarker;
-use service::StackStorage;
+use instrument::Spans;
+use marker::{TopLevelMarker, Transaction};
+use middleware::Chain;
+use registry::{Edge, EdgeKind};
+use service::{Clock, Journal, Rng, StackStorage, Stopwatch};
 use state::*;
-use stm::{PullupFrom, PushdownFrom, TransitionFrom};
+use stm::{Checked, PullupFrom, PushdownFrom, TransitionFrom};
+use trace::Trace;
 use transaction::{Epsilon, PrintTransaction, TransactionItem};
 
 /////////////////////
@@ -648,13 +1317,15 @@ use transaction::{Epsilon, PrintTransaction, TransactionItem};
 /// The state machine.
 ///
 /// The developer is encouraged to design this structure in any desired
-/// way by storing services into it's members.
+/// way by storing services into it's members. Data which doesn't belong to
+/// the machine itself is stored in the `context` field, see [`Context`].
 /// Each state machine MUST have a `state` and `transaction` field AT
 /// MINIMUM.
 #[derive(Debug, Clone)]
-pub struct Machine<X>
+pub struct Machine<X, C = ()>
 where
     X: TopLevelMarker + State,
+    C: Context,
 {
     /* Absolute minimum variables */
     /// Field to encode the current state of the machine.
@@ -671,18 +1342,39 @@ where
     /// Stack storage service to allow PushDown and Pullup behaviour to be
     /// implemented.
     pub storage: StackStorage<TransactionItem>,
+    /// Journal service recording each applied transition.
+    pub journal: Journal,
+    /// Random number generator service, recorded by the journal.
+    pub rng: Rng,
+    /// Spans of the `tracing` feature, see [`instrument`].
+    pub spans: Spans,
+    /// Trace service recording each entered state.
+    pub trace: Trace,
+    /// Stopwatch service timestamping the moment the current state was entered.
+    pub stopwatch: Stopwatch,
+    /// Middleware service wrapping each edge applied onto the dynamic machine, see
+    /// [`middleware`].
+    ///
+    /// The typed transitions don't consult it, the chain is only carried along so it
+    /// stays installed once the machine is wrapped into an
+    /// [`AnyMachine`](::dynamic::AnyMachine).
+    pub middleware: Chain<C>,
+    /// User defined data, carried across every transition.
+    pub context: C,
 }
 
-impl<X> StateContainer for Machine<X>
+impl<X, C> StateContainer for Machine<X, C>
 where
     X: TopLevelMarker + State,
+    C: Context,
 {
     type State = X;
 }
 
-impl<X> ServiceCompliance<StackStorage<TransactionItem>> for Machine<X>
+impl<X, C> ServiceCompliance<StackStorage<TransactionItem>> for Machine<X, C>
 where
     X: TopLevelMarker + State,
+    C: Context,
 {
     fn get(&self) -> &StackStorage<TransactionItem> {
         &self.storage
@@ -693,59 +1385,250 @@ where
     }
 }
 
-////////////////////////////////
-// Transition implementations //
-////////////////////////////////
+impl<X, C> ServiceCompliance<Journal> for Machine<X, C>
+where
+    X: TopLevelMarker + State,
+    C: Context,
+{
+    fn get(&self) -> &Journal {
+        &self.journal
+    }
 
-/* Machine<Wait<Start>> -> Machine<Wait<Input>> */
-impl TransitionFrom<Machine<Wait<Start>>> for Machine<Wait<Input>> {
-    fn transition_from(old: Machine<Wait<Start>>, t: <Self::State as State>::Transaction) -> Self {
+    fn get_mut(&mut self) -> &mut Journal {
+        &mut self.journal
+    }
+}
+
+impl<X, C> ServiceCompliance<Rng> for Machine<X, C>
+where
+    X: TopLevelMarker + State,
+    C: Context,
+{
+    fn get(&self) -> &Rng {
+        &self.rng
+    }
+
+    fn get_mut(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+}
+
+impl Machine<Wait<Start>> {
+    /// Build a new state machine residing in the initial state.
+    pub fn new() -> Self {
+        Self::with_context(())
+    }
+
+    /// Build a new state machine residing in the initial state, generating random
+    /// numbers from the provided seed.
+    pub fn with_seed(seed: u64) -> Self {
         Machine {
-            state: PhantomData,
-            transaction: t,
-            // Following properties MUST stay in sync with `Machine` !
-            storage: old.storage,
+            rng: Rng::new(seed),
+            ..Self::new()
+        }
+    }
+
+    /// Build a new state machine residing in the initial state, timestamping each entered
+    /// state with the provided clock.
+    pub fn with_clock<K>(clock: K) -> Self
+    where
+        K: Clock + Send + Sync + 'static,
+    {
+        Machine {
+            stopwatch: Stopwatch::new(clock),
+            ..Self::new()
         }
     }
 }
 
-/* Machine<Wait<Input>> -> Machine<Finished> */
-impl TransitionFrom<Machine<Wait<Input>>> for Machine<Finished> {
-    fn transition_from(old: Machine<Wait<Input>>, t: <Self::State as State>::Transaction) -> Self {
+impl<C> Machine<Wait<Start>, C>
+where
+    C: Context,
+{
+    /// Build a new state machine residing in the initial state, carrying the provided
+    /// context.
+    pub fn with_context(context: C) -> Self {
         Machine {
             state: PhantomData,
-            transaction: t,
-            // Following properties MUST stay in sync with `Machine` !
-            storage: old.storage,
+            transaction: Epsilon,
+            storage: StackStorage { tape: vec![] },
+            journal: Journal::default(),
+            rng: Rng::default(),
+            spans: Spans::default(),
+            trace: Trace::default(),
+            stopwatch: Stopwatch::default(),
+            middleware: Chain::default(),
+            context,
+        }
+    }
+}
+
+impl<C> Default for Machine<Wait<Start>, C>
+where
+    C: Context + Default,
+{
+    fn default() -> Self {
+        Self::with_context(C::default())
+    }
+}
+
+impl<X, C> Machine<X, C>
+where
+    X: TopLevelMarker + State,
+    C: Context,
+{
+    /// Move the services and context into a machine residing in the next state.
+    ///
+    /// Applies the bookkeeping shared by every edge: the hooks of the context, the
+    /// journal, spans, trace and stopwatch. Pushdowns archive the Transaction of the
+    /// current state into storage, the other edges drop it.
+    fn carry<Y>(mut self, edge: Edge, transaction: Y::Transaction) -> Machine<Y, C>
+    where
+        Y: TopLevelMarker + State,
+        X::Transaction: Transaction + Into<TransactionItem> + 'static,
+        Y::Transaction: Clone + Display + Into<TransactionItem>,
+    {
+        self.context.on_exit(edge);
+
+        // Record the applied edge, pullups restore their Transaction from storage.
+        let recorded = match edge.kind {
+            EdgeKind::Pullup => None,
+            _ => Some(transaction.to_string()),
+        };
+        let rng = *ServiceCompliance::<Rng>::get(&self);
+        ServiceCompliance::<Journal>::get_mut(&mut self).record(edge, recorded, Some(rng));
+        match edge.kind {
+            EdgeKind::Transition => self.spans.transition(edge, &transaction),
+            EdgeKind::Pushdown => self.spans.pushdown(edge, &transaction),
+            EdgeKind::Pullup => self.spans.pullup(edge, &transaction),
         }
+
+        if edge.kind == EdgeKind::Pushdown {
+            // Archive state of the old machine.
+            // The transaction is moved out of the machine, so the storage is accessed
+            // directly. Going through `ServiceCompliance` would require borrowing the
+            // machine as a whole.
+            let old_transaction: TransactionItem = pack_transaction(self.transaction);
+            self.storage
+                .push(old_transaction)
+                .expect("Never type triggered!");
+        }
+
+        let mut new: Machine<Y, C> = Machine {
+            state: PhantomData,
+            transaction,
+            // Following properties MUST stay in sync with `Machine` !
+            storage: self.storage,
+            journal: self.journal,
+            rng: self.rng,
+            spans: self.spans,
+            trace: self.trace,
+            stopwatch: self.stopwatch,
+            middleware: self.middleware,
+            context: self.context,
+        };
+        // Trace and timestamp the entered state.
+        let item: TransactionItem = new.transaction.clone().into();
+        let depth = new.storage.tape.len();
+        new.trace.record(edge, item, depth);
+        new.stopwatch.restart();
+        new.context.on_entry(edge);
+        new
+    }
+}
+
+impl<C> Machine<Finished, C>
+where
+    C: Context,
+{
+    /// Consume the finished state machine and retrieve the data it ended with, including
+    /// the context.
+    pub fn into_result(
+        self,
+    ) -> (<Finished as State>::Transaction, StackStorage<TransactionItem>, C) {
+        (self.transaction, self.storage, self.context)
+    }
+}
+
+////////////////////////////////
+// Transition implementations //
+////////////////////////////////
+
+/* Machine<Wait<Start>> -> Machine<Wait<Input>> */
+impl<C> TransitionFrom<Machine<Wait<Start>, C>> for Machine<Wait<Input>, C>
+where
+    C: Context,
+{
+    fn transition_from(
+        old: Machine<Wait<Start>, C>,
+        t: <Self::State as State>::Transaction,
+        _: Checked,
+    ) -> Self {
+        old.carry(Edge::START_TO_INPUT, t)
+    }
+
+    fn guard(
+        old: &Machine<Wait<Start>, C>,
+        t: &<Self::State as State>::Transaction,
+    ) -> Result<(), MachineError> {
+        old.context
+            .guard(Edge::START_TO_INPUT, Some(&TransactionItem::from(*t)))
+            .context(ErrorKind::ConstraintError, old)
+    }
+}
+
+/* Machine<Wait<Input>> -> Machine<Finished> */
+impl<C> TransitionFrom<Machine<Wait<Input>, C>> for Machine<Finished, C>
+where
+    C: Context,
+{
+    fn transition_from(
+        old: Machine<Wait<Input>, C>,
+        t: <Self::State as State>::Transaction,
+        _: Checked,
+    ) -> Self {
+        old.carry(Edge::INPUT_TO_FINISHED, t)
+    }
+
+    fn guard(
+        old: &Machine<Wait<Input>, C>,
+        t: &<Self::State as State>::Transaction,
+    ) -> Result<(), MachineError> {
+        old.context
+            .guard(Edge::INPUT_TO_FINISHED, Some(&TransactionItem::from(*t)))
+            .context(ErrorKind::ConstraintError, old)
     }
 }
 
 /* Machine<Wait<Input>> <-> Machine<Action<Print>> */
-impl PushdownFrom<Machine<Wait<Input>>, TransactionItem> for Machine<Action<Print>> {
+impl<C> PushdownFrom<Machine<Wait<Input>, C>, TransactionItem> for Machine<Action<Print>, C>
+where
+    C: Context,
+{
     fn pushdown_from(
-        mut old: Machine<Wait<Input>>,
+        old: Machine<Wait<Input>, C>,
         t: <Self::State as State>::Transaction,
+        _: Checked,
     ) -> Self {
-        // Archive state of the old machine.
-        let old_transaction: TransactionItem = pack_transaction(old.transaction);
-        ServiceCompliance::<StackStorage<TransactionItem>>::get_mut(&mut old)
-            .push(old_transaction)
-            .expect("Never type triggered!");
+        old.carry(Edge::INPUT_PUSHDOWN_PRINT, t)
+    }
 
-        // Build new machine.
-        Machine {
-            state: PhantomData,
-            transaction: t,
-            // Following properties MUST stay in sync with `Machine` !
-            storage: old.storage,
-        }
+    fn guard(
+        old: &Machine<Wait<Input>, C>,
+        t: &<Self::State as State>::Transaction,
+    ) -> Result<(), MachineError> {
+        old.context
+            .guard(Edge::INPUT_PUSHDOWN_PRINT, Some(&TransactionItem::from(t.clone())))
+            .context(ErrorKind::ConstraintError, old)
     }
 }
 
 /* Machine<Wait<Input>> <-> Machine<Action<Print>> */
-impl PullupFrom<Machine<Action<Print>>, TransactionItem> for Machine<Wait<Input>> {
-    fn pullup_from(mut old: Machine<Action<Print>>) -> Result<Self, MachineError> {
+impl<C> PullupFrom<Machine<Action<Print>, C>, TransactionItem> for Machine<Wait<Input>, C>
+where
+    C: Context,
+{
+    fn pullup_from(mut old: Machine<Action<Print>, C>, _: Checked) -> Result<Self, MachineError> {
         // Restore previously stored state.
         let old_transaction = ServiceCompliance::<StackStorage<TransactionItem>>::get_mut(&mut old)
             .pop()
@@ -755,41 +1638,45 @@ impl PullupFrom<Machine<Action<Print>>, TransactionItem> for Machine<Wait<Input>
         // DBG
         // let old_transaction = Epsilon;
 
-        // Build new machine.
-        Ok(Machine {
-            state: PhantomData,
-            transaction: old_transaction,
-            // Following properties MUST stay in sync with `Machine` !
-            storage: old.storage,
-        })
+        Ok(old.carry(Edge::PRINT_PULLUP_INPUT, old_transaction))
+    }
+
+    fn guard(old: &Machine<Action<Print>, C>) -> Result<(), MachineError> {
+        old.context
+            .guard(Edge::PRINT_PULLUP_INPUT, None)
+            .context(ErrorKind::ConstraintError, old)
     }
 }
 
 /* Machine<Action<Print>> <-> Machine<Action<Load>> */
-impl PushdownFrom<Machine<Action<Print>>, TransactionItem> for Machine<Action<Load>> {
+impl<C> PushdownFrom<Machine<Action<Print>, C>, TransactionItem> for Machine<Action<Load>, C>
+where
+    C: Context,
+{
     fn pushdown_from(
-        mut old: Machine<Action<Print>>,
+        old: Machine<Action<Print>, C>,
         t: <Self::State as State>::Transaction,
+        _: Checked,
     ) -> Self {
-        // Archive state of the old machine.
-        let old_transaction: TransactionItem = pack_transaction(old.transaction);
-        ServiceCompliance::<StackStorage<TransactionItem>>::get_mut(&mut old)
-            .push(old_transaction)
-            .expect("Never type triggered!");
+        old.carry(Edge::PRINT_PUSHDOWN_LOAD, t)
+    }
 
-        // Build new machine.
-        Machine {
-            state: PhantomData,
-            transaction: t,
-            // Following properties MUST stay in sync with `Machine` !
-            storage: old.storage,
-        }
+    fn guard(
+        old: &Machine<Action<Print>, C>,
+        t: &<Self::State as State>::Transaction,
+    ) -> Result<(), MachineError> {
+        old.context
+            .guard(Edge::PRINT_PUSHDOWN_LOAD, Some(&TransactionItem::from(*t)))
+            .context(ErrorKind::ConstraintError, old)
     }
 }
 
 /* Machine<Action<Print>> <-> Machine<Action<Load>> */
-impl PullupFrom<Machine<Action<Load>>, TransactionItem> for Machine<Action<Print>> {
-    fn pullup_from(mut old: Machine<Action<Load>>) -> Result<Self, MachineError> {
+impl<C> PullupFrom<Machine<Action<Load>, C>, TransactionItem> for Machine<Action<Print>, C>
+where
+    C: Context,
+{
+    fn pullup_from(mut old: Machine<Action<Load>, C>, _: Checked) -> Result<Self, MachineError> {
         // Restore previously stored state.
         let old_transaction = ServiceCompliance::<StackStorage<TransactionItem>>::get_mut(&mut old)
             .pop()
@@ -797,14 +1684,39 @@ impl PullupFrom<Machine<Action<Load>>, TransactionItem> for Machine<Action<Print
             .and_then(|item| unpack_transaction(item).context(ErrorKind::ConstraintError, &old))?;
 
         // DBG
-        // let old_transaction = PrintTransaction("dbg");
+        // let old_transaction = PrintTransaction("dbg".to_string());
 
-        // Build new machine.
-        Ok(Machine {
-            state: PhantomData,
-            transaction: old_transaction,
-            // Following properties MUST stay in sync with `Machine` !
-            storage: old.storage,
-        })
+        Ok(old.carry(Edge::LOAD_PULLUP_PRINT, old_transaction))
+    }
+
+    fn guard(old: &Machine<Action<Load>, C>) -> Result<(), MachineError> {
+        old.context
+            .guard(Edge::LOAD_PULLUP_PRINT, None)
+            .context(ErrorKind::ConstraintError, old)
     }
 }
+
+/////////////////////////
+// Transition registry //
+/////////////////////////
+
+/// Description of all transitions implemented for [`Machine`].
+///
+/// Each implementation of [`TransitionFrom`], [`PushdownFrom`] and [`PullupFrom`] above
+/// MUST have a matching entry in this list!
+///
+/// ```
+/// use automaton_test::TRANSITIONS;
+/// use automaton_test::registry::analysis::Analysis;
+///
+/// let analysis = Analysis::of(TRANSITIONS);
+/// assert!(analysis.is_sound(), "{:?}", analysis.defects());
+/// ```
+pub const TRANSITIONS: &[Edge] = &[
+    Edge::START_TO_INPUT,
+    Edge::INPUT_TO_FINISHED,
+    Edge::INPUT_PUSHDOWN_PRINT,
+    Edge::PRINT_PULLUP_INPUT,
+    Edge::PRINT_PUSHDOWN_LOAD,
+    Edge::LOAD_PULLUP_PRINT,
+];
@@ -0,0 +1,311 @@
+//! Cross-cutting behaviour wrapped around each edge applied onto a dynamic machine.
+//!
+//! A [`Chain`] holds layers of [`Middleware`], which are consulted in order before an
+//! edge is applied and in reverse order afterwards. Any layer can veto the edge, in
+//! which case the machine is left untouched and the layers consulted so far observe
+//! the veto as result.
+//!
+//! The chain is a service installed on each machine, so [`AnyMachine::apply`] and
+//! [`AnyMachine::execute`] run through it, as does every driver built upon them. The
+//! typed transitions of [`Machine`](::Machine) don't consult it.
+//!
+//! Each layer is locked while it's consulted, the chain itself is not. Edges applied
+//! from within a layer, onto a machine sharing the chain, don't consult the layers
+//! again.
+//!
+//! ```
+//! use std::sync::{Arc, Mutex};
+//!
+//! use automaton_test::dynamic::AnyMachine;
+//! use automaton_test::function::error::{ErrorKind, MachineError, RuntimeConstraintError};
+//! use automaton_test::middleware::{Middleware, Step};
+//! use automaton_test::registry::EdgeKind;
+//!
+//! /// Collects a line for each edge.
+//! struct Log(Arc<Mutex<Vec<String>>>);
+//!
+//! impl Middleware for Log {
+//!     fn after(&mut self, _: &AnyMachine, step: &Step, result: Result<(), &MachineError>) {
+//!         let outcome = if result.is_ok() { "applied" } else { "failed" };
+//!         self.0.lock().unwrap().push(format!("{} {}", step.edge, outcome));
+//!     }
+//! }
+//!
+//! let lines = Arc::new(Mutex::new(vec![]));
+//! let mut machine: AnyMachine = AnyMachine::default();
+//! machine.middleware_mut().push(Log(lines.clone()));
+//! // Forbid pushing down deeper than one level.
+//! machine
+//!     .middleware_mut()
+//!     .push(|machine: &AnyMachine, step: &Step| match step.edge.kind {
+//!         EdgeKind::Pushdown if !machine.storage().tape.is_empty() => {
+//!             let error = RuntimeConstraintError::from(("one level of pushdown", step.edge));
+//!             Err(machine.error(ErrorKind::ConstraintError, error))
+//!         }
+//!         _ => Ok(()),
+//!     });
+//!
+//! // Drivers, like following epsilon edges, run through the layers as well.
+//! machine.follow_epsilon().unwrap();
+//! machine.execute(r#"push print "Hello""#.parse().unwrap()).unwrap();
+//! assert!(machine.execute("push load".parse().unwrap()).is_err());
+//! assert_eq!(machine.storage().tape.len(), 1);
+//! assert_eq!(lines.lock().unwrap().len(), 3);
+//! assert!(lines.lock().unwrap()[2].ends_with("failed"));
+//! ```
+
+use std::cell::RefCell;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use acl::Actor;
+use command::Command;
+use context::Context;
+use dynamic::AnyMachine;
+use function::error::MachineError;
+use marker::Service;
+use registry::Edge;
+use transaction::TransactionItem;
+
+/// Description of the edge which is about to be, or was, applied.
+///
+/// The source state, target state and kind of the edge are held by [`Step::edge`].
+#[derive(Debug, Clone)]
+pub struct Step {
+    /// The applied edge.
+    pub edge: Edge,
+    /// Transaction for the target state, pullup edges restore it from storage instead.
+    pub transaction: Option<TransactionItem>,
+    /// The party which dispatched the edge, see [`AnyMachine::dispatch`].
+    pub actor: Option<Actor>,
+}
+
+/// One layer of behaviour wrapped around each applied edge.
+///
+/// Each hook has a default implementation which does nothing.
+pub trait Middleware<C = ()>
+where
+    C: Context,
+{
+    /// Consulted before the edge is applied, with the machine residing in the source
+    /// state.
+    ///
+    /// # Errors
+    /// Returning an error vetoes the edge, the error is reported to the caller of the
+    /// chain.
+    fn before(&mut self, machine: &AnyMachine<C>, step: &Step) -> Result<(), MachineError> {
+        Ok(())
+    }
+
+    /// Observes the result of applying the edge.
+    ///
+    /// The machine resides in the target state when the edge was applied, and in the
+    /// source state otherwise.
+    fn after(&mut self, machine: &AnyMachine<C>, step: &Step, result: Result<(), &MachineError>) {}
+
+    /// Observes a command which doesn't describe an edge from the current state, so no
+    /// edge is applied.
+    fn unresolved(
+        &mut self,
+        machine: &AnyMachine<C>,
+        command: &Command,
+        actor: Option<&Actor>,
+        error: &MachineError,
+    ) {
+    }
+}
+
+/// Closures act as layers which only veto edges.
+impl<C, F> Middleware<C> for F
+where
+    C: Context,
+    F: FnMut(&AnyMachine<C>, &Step) -> Result<(), MachineError>,
+{
+    fn before(&mut self, machine: &AnyMachine<C>, step: &Step) -> Result<(), MachineError> {
+        self(machine, step)
+    }
+}
+
+/// One installed layer, locked while it's consulted.
+type Layer<C> = Arc<Mutex<Box<dyn Middleware<C> + Send>>>;
+
+thread_local! {
+    /// Chains consulted by the current thread, identified by the address of their layers.
+    static CONSULTING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Service holding ordered layers of [`Middleware`], see [`AnyMachine::middleware_mut`].
+///
+/// Clones share the installed layers, so the layers stay installed across every edge
+/// and within the snapshot of each error.
+#[derive(Clone)]
+pub struct Chain<C = ()>
+where
+    C: Context,
+{
+    layers: Arc<Mutex<Vec<Layer<C>>>>,
+}
+
+impl<C> Debug for Chain<C>
+where
+    C: Context,
+{
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Chain")
+            .field("layers", &self.len())
+            .finish()
+    }
+}
+
+impl<C> Default for Chain<C>
+where
+    C: Context,
+{
+    fn default() -> Self {
+        Chain {
+            layers: Arc::new(Mutex::new(vec![])),
+        }
+    }
+}
+
+impl<C> Service for Chain<C> where C: Context {}
+
+impl<C> Chain<C>
+where
+    C: Context,
+{
+    /// Build a chain without layers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the provided layer, it's consulted after all existing layers.
+    pub fn push<M>(&mut self, layer: M)
+    where
+        M: Middleware<C> + Send + 'static,
+    {
+        self.lock().push(Arc::new(Mutex::new(Box::new(layer))));
+    }
+
+    /// Amount of layers.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether the chain holds no layers.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock<'a>(&'a self) -> MutexGuard<'a, Vec<Layer<C>>> {
+        self.layers.lock().expect("Middleware lock poisoned")
+    }
+
+    /// Apply the edge of the provided step onto the machine, wrapped by each layer, see
+    /// [`AnyMachine::apply`].
+    ///
+    /// # Errors
+    /// A veto of any layer is returned unchanged, the remaining layers are not
+    /// consulted. Failures of the edge itself are returned after all layers observed
+    /// them.
+    pub(crate) fn apply(
+        &self,
+        machine: &mut AnyMachine<C>,
+        step: Step,
+    ) -> Result<(), MachineError> {
+        match self.consult() {
+            Some(consultation) => wrap(&consultation.layers, machine, step),
+            None => machine.apply_unwrapped(step.edge, step.transaction),
+        }
+    }
+
+    /// Let each layer observe the provided command, which doesn't describe an edge from
+    /// the current state.
+    pub(crate) fn unresolved(
+        &self,
+        machine: &AnyMachine<C>,
+        command: &Command,
+        actor: Option<&Actor>,
+        error: &MachineError,
+    ) {
+        if let Some(consultation) = self.consult() {
+            for layer in &consultation.layers {
+                lock(layer).unresolved(machine, command, actor, error);
+            }
+        }
+    }
+
+    /// Snapshot of the installed layers, unless the current thread is consulting them
+    /// already.
+    ///
+    /// Edges applied from within a layer don't consult the layers again, the layer
+    /// consulting them is locked. Layers installed during a consultation are consulted
+    /// from the next edge on.
+    fn consult(&self) -> Option<Consultation<C>> {
+        let chain = &*self.layers as *const Mutex<_> as usize;
+        let entered = CONSULTING.with(|consulting| {
+            let mut consulting = consulting.borrow_mut();
+            if consulting.contains(&chain) {
+                return false;
+            }
+            consulting.push(chain);
+            true
+        });
+        if !entered {
+            return None;
+        }
+        // The chain isn't locked while its layers are consulted.
+        let layers = self.lock().clone();
+        Some(Consultation { chain, layers })
+    }
+}
+
+/// Layers of a chain consulted by the current thread, see [`Chain::consult`].
+///
+/// The chain is no longer marked as consulted once dropped, even when a layer panicked.
+struct Consultation<C>
+where
+    C: Context,
+{
+    chain: usize,
+    layers: Vec<Layer<C>>,
+}
+
+impl<C> Drop for Consultation<C>
+where
+    C: Context,
+{
+    fn drop(&mut self) {
+        let chain = self.chain;
+        CONSULTING.with(|consulting| consulting.borrow_mut().retain(|&c| c != chain));
+    }
+}
+
+fn lock<'a, C>(layer: &'a Layer<C>) -> MutexGuard<'a, Box<dyn Middleware<C> + Send>>
+where
+    C: Context,
+{
+    layer.lock().expect("Middleware lock poisoned")
+}
+
+fn wrap<C>(layers: &[Layer<C>], machine: &mut AnyMachine<C>, step: Step) -> Result<(), MachineError>
+where
+    C: Context,
+{
+    for (index, layer) in layers.iter().enumerate() {
+        let verdict = lock(layer).before(machine, &step);
+        if let Err(veto) = verdict {
+            // Only the layers which saw the edge observe the veto.
+            for layer in layers[..index].iter().rev() {
+                lock(layer).after(machine, &step, Err(&veto));
+            }
+            return Err(veto);
+        }
+    }
+
+    let result = machine.apply_unwrapped(step.edge, step.transaction.clone());
+    for layer in layers.iter().rev() {
+        lock(layer).after(machine, &step, result.as_ref().map(|_| ()));
+    }
+    result
+}
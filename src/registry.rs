@@ -0,0 +1,399 @@
+//! Types describing the declared state graph of a state machine.
+//!
+//! The type system verifies each transition on its own, but it has no notion of the
+//! graph formed by all implemented transitions together. The registry is a runtime
+//! description of that graph, which allows analysis of the flow as a whole.
+
+use std::any::TypeId;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::Hash;
+use std::str::FromStr;
+
+use function::error::RuntimeConstraintError;
+use function::{Fields, State};
+use state::*;
+use transaction::Epsilon;
+
+/// Types identifying each top-level state of a state machine.
+///
+/// [`StateId`] identifies the states of this crate, [`codegen`](::codegen) generates
+/// an implementation for each specification.
+pub trait StateSet: Copy + Eq + Hash + Debug + Display + 'static {
+    /// Collection of all known states.
+    const ALL: &'static [Self];
+
+    /// The state each state machine starts from.
+    const INITIAL: Self;
+
+    /// Returns true if the state is not allowed to have any outgoing transitions.
+    fn is_terminal(self) -> bool;
+}
+
+/// Identification of each top-level state a [`Machine`] can reside in.
+///
+/// [`Machine`]: ::Machine
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum StateId {
+    /// See [`Wait`](::state::Wait) and [`Start`](::state::Start).
+    WaitStart,
+    /// See [`Wait`](::state::Wait) and [`Input`](::state::Input).
+    WaitInput,
+    /// See [`Action`](::state::Action) and [`Load`](::state::Load).
+    ActionLoad,
+    /// See [`Action`](::state::Action) and [`Print`](::state::Print).
+    ActionPrint,
+    /// See [`Finished`](::state::Finished).
+    Finished,
+}
+
+impl StateId {
+    /// Collection of all known states.
+    pub const ALL: &'static [StateId] = &[
+        StateId::WaitStart,
+        StateId::WaitInput,
+        StateId::ActionLoad,
+        StateId::ActionPrint,
+        StateId::Finished,
+    ];
+
+    /// The state each state machine starts from.
+    pub const INITIAL: StateId = StateId::WaitStart;
+
+    /// Returns true if the state is not allowed to have any outgoing transitions.
+    pub fn is_terminal(self) -> bool {
+        self == StateId::Finished
+    }
+
+    /// Returns true if the state is a [`Wait`](::state::Wait) state.
+    pub fn is_waitable(self) -> bool {
+        self == StateId::WaitStart || self == StateId::WaitInput
+    }
+
+    /// Returns true if the state is an [`Action`](::state::Action) state.
+    pub fn is_actionable(self) -> bool {
+        self == StateId::ActionLoad || self == StateId::ActionPrint
+    }
+
+    /// Returns true if the state is entered with an [`Epsilon`](::transaction::Epsilon)
+    /// Transaction.
+    pub fn takes_epsilon(self) -> bool {
+        self.transaction().0 == TypeId::of::<Epsilon>()
+    }
+
+    /// Names of the fields of the Transaction of this state, see [`Fields`].
+    pub fn fields(self) -> &'static [&'static str] {
+        self.transaction().1
+    }
+
+    /// Type and fields of the Transaction of this state, derived from the state type.
+    fn transaction(self) -> (TypeId, &'static [&'static str]) {
+        fn of<X>() -> (TypeId, &'static [&'static str])
+        where
+            X: State,
+            X::Transaction: Fields + 'static,
+        {
+            (TypeId::of::<X::Transaction>(), X::Transaction::FIELDS)
+        }
+
+        match self {
+            StateId::WaitStart => of::<Wait<Start>>(),
+            StateId::WaitInput => of::<Wait<Input>>(),
+            StateId::ActionLoad => of::<Action<Load>>(),
+            StateId::ActionPrint => of::<Action<Print>>(),
+            StateId::Finished => of::<Finished>(),
+        }
+    }
+}
+
+impl StateSet for StateId {
+    const ALL: &'static [StateId] = StateId::ALL;
+
+    const INITIAL: StateId = StateId::INITIAL;
+
+    fn is_terminal(self) -> bool {
+        StateId::is_terminal(self)
+    }
+}
+
+impl Display for StateId {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let name = match *self {
+            StateId::WaitStart => "Wait<Start>",
+            StateId::WaitInput => "Wait<Input>",
+            StateId::ActionLoad => "Action<Load>",
+            StateId::ActionPrint => "Action<Print>",
+            StateId::Finished => "Finished",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for StateId {
+    type Err = RuntimeConstraintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        StateId::ALL
+            .iter()
+            .cloned()
+            .find(|id| id.to_string() == s.trim())
+            .ok_or_else(|| ("name of a known state", s).into())
+    }
+}
+
+/// The kind of movement an edge represents, matching the traits within [`stm`](::stm).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    /// See [`TransitionFrom`](::stm::TransitionFrom).
+    Transition,
+    /// See [`PushdownFrom`](::stm::PushdownFrom).
+    Pushdown,
+    /// See [`PullupFrom`](::stm::PullupFrom).
+    Pullup,
+}
+
+/// Description of one implemented transition between two states.
+///
+/// An edge is uniquely identified by its kind and both states. The textual
+/// representation, through [`Display`] and [`FromStr`], can be used to store edges
+/// outside of the running program.
+///
+/// Edges between the states of a generated state machine identify those through
+/// another [`StateSet`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Edge<S = StateId>
+where
+    S: StateSet,
+{
+    /// The kind of transition.
+    pub kind: EdgeKind,
+    /// The state the machine must reside in before transitioning.
+    pub from: S,
+    /// The state the machine resides in after transitioning.
+    pub to: S,
+}
+
+impl Edge {
+    /* Machine<Wait<Start>> -> Machine<Wait<Input>> */
+    /// See [`TRANSITIONS`](::TRANSITIONS).
+    pub const START_TO_INPUT: Edge = Edge {
+        kind: EdgeKind::Transition,
+        from: StateId::WaitStart,
+        to: StateId::WaitInput,
+    };
+    /* Machine<Wait<Input>> -> Machine<Finished> */
+    /// See [`TRANSITIONS`](::TRANSITIONS).
+    pub const INPUT_TO_FINISHED: Edge = Edge {
+        kind: EdgeKind::Transition,
+        from: StateId::WaitInput,
+        to: StateId::Finished,
+    };
+    /* Machine<Wait<Input>> <-> Machine<Action<Print>> */
+    /// See [`TRANSITIONS`](::TRANSITIONS).
+    pub const INPUT_PUSHDOWN_PRINT: Edge = Edge {
+        kind: EdgeKind::Pushdown,
+        from: StateId::WaitInput,
+        to: StateId::ActionPrint,
+    };
+    /// See [`TRANSITIONS`](::TRANSITIONS).
+    pub const PRINT_PULLUP_INPUT: Edge = Edge {
+        kind: EdgeKind::Pullup,
+        from: StateId::ActionPrint,
+        to: StateId::WaitInput,
+    };
+    /* Machine<Action<Print>> <-> Machine<Action<Load>> */
+    /// See [`TRANSITIONS`](::TRANSITIONS).
+    pub const PRINT_PUSHDOWN_LOAD: Edge = Edge {
+        kind: EdgeKind::Pushdown,
+        from: StateId::ActionPrint,
+        to: StateId::ActionLoad,
+    };
+    /// See [`TRANSITIONS`](::TRANSITIONS).
+    pub const LOAD_PULLUP_PRINT: Edge = Edge {
+        kind: EdgeKind::Pullup,
+        from: StateId::ActionLoad,
+        to: StateId::ActionPrint,
+    };
+}
+
+impl<S> Display for Edge<S>
+where
+    S: StateSet,
+{
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let arrow = match self.kind {
+            EdgeKind::Transition => "->",
+            EdgeKind::Pushdown => "=>",
+            EdgeKind::Pullup => "<=",
+        };
+        match self.kind {
+            // Pullup edges are written in the same direction as their matching pushdown.
+            EdgeKind::Pullup => write!(f, "{} {} {}", self.to, arrow, self.from),
+            _ => write!(f, "{} {} {}", self.from, arrow, self.to),
+        }
+    }
+}
+
+impl FromStr for Edge {
+    type Err = RuntimeConstraintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let arrows = [
+            (" -> ", EdgeKind::Transition),
+            (" => ", EdgeKind::Pushdown),
+            (" <= ", EdgeKind::Pullup),
+        ];
+        let (idx, arrow, kind) = arrows
+            .iter()
+            .filter_map(|&(arrow, kind)| s.find(arrow).map(|idx| (idx, arrow, kind)))
+            .next()
+            .ok_or_else(|| RuntimeConstraintError::from(("`<state> <arrow> <state>`", s)))?;
+        let left: StateId = s[..idx].parse()?;
+        let right: StateId = s[idx + arrow.len()..].parse()?;
+        let (from, to) = match kind {
+            // Pullup edges are written in the same direction as their matching pushdown.
+            EdgeKind::Pullup => (right, left),
+            _ => (left, right),
+        };
+        Ok(Edge { kind, from, to })
+    }
+}
+
+pub mod analysis {
+    //! Checks over the declared state graph.
+    //!
+    //! These checks are intended to be executed from unit tests, so mistakes in the
+    //! declared flow are caught before the state machine is ever run.
+
+    use std::collections::{HashSet, VecDeque};
+
+    use super::{Edge, EdgeKind, StateId, StateSet};
+
+    /// Result of analyzing a set of edges.
+    #[derive(Debug, Clone)]
+    pub struct Analysis<S = StateId>
+    where
+        S: StateSet,
+    {
+        reachable: Vec<S>,
+        unreachable: Vec<S>,
+        dead_ends: Vec<S>,
+        unmatched_pushdowns: Vec<Edge<S>>,
+        terminal_exits: Vec<Edge<S>>,
+    }
+
+    impl<S> Analysis<S>
+    where
+        S: StateSet,
+    {
+        /// Analyze the graph formed by the provided edges, starting from
+        /// [`StateSet::INITIAL`].
+        pub fn of(edges: &[Edge<S>]) -> Self {
+            // Breadth first walk over all edges, regardless of their kind.
+            let mut visited = HashSet::new();
+            let mut queue = VecDeque::new();
+            visited.insert(S::INITIAL);
+            queue.push_back(S::INITIAL);
+            while let Some(state) = queue.pop_front() {
+                for edge in edges.iter().filter(|e| e.from == state) {
+                    if visited.insert(edge.to) {
+                        queue.push_back(edge.to);
+                    }
+                }
+            }
+
+            let (reachable, unreachable) = S::ALL
+                .iter()
+                .cloned()
+                .partition(|s| visited.contains(s));
+
+            let dead_ends = S::ALL
+                .iter()
+                .cloned()
+                .filter(|s| !s.is_terminal())
+                .filter(|s| !edges.iter().any(|e| e.from == *s))
+                .collect();
+
+            let unmatched_pushdowns = edges
+                .iter()
+                .filter(|e| e.kind == EdgeKind::Pushdown)
+                .filter(|push| {
+                    !edges.iter().any(|e| {
+                        e.kind == EdgeKind::Pullup && e.from == push.to && e.to == push.from
+                    })
+                })
+                .cloned()
+                .collect();
+
+            let terminal_exits = edges
+                .iter()
+                .filter(|e| e.from.is_terminal())
+                .cloned()
+                .collect();
+
+            Analysis {
+                reachable,
+                unreachable,
+                dead_ends,
+                unmatched_pushdowns,
+                terminal_exits,
+            }
+        }
+
+        /// States which can be reached from the initial state.
+        pub fn reachable(&self) -> &[S] {
+            &self.reachable
+        }
+
+        /// States which can never be reached from the initial state.
+        pub fn unreachable(&self) -> &[S] {
+            &self.unreachable
+        }
+
+        /// Non-terminal states without any outgoing edge.
+        pub fn dead_ends(&self) -> &[S] {
+            &self.dead_ends
+        }
+
+        /// Pushdown edges for which no pullup edge in the opposite direction exists.
+        pub fn unmatched_pushdowns(&self) -> &[Edge<S>] {
+            &self.unmatched_pushdowns
+        }
+
+        /// Edges leaving a terminal state.
+        pub fn terminal_exits(&self) -> &[Edge<S>] {
+            &self.terminal_exits
+        }
+
+        /// Returns true if none of the checks detected a defect.
+        pub fn is_sound(&self) -> bool {
+            self.defects().is_empty()
+        }
+
+        /// Human readable description of each detected defect.
+        pub fn defects(&self) -> Vec<String> {
+            let mut defects = vec![];
+            defects.extend(
+                self.unreachable
+                    .iter()
+                    .map(|s| format!("State `{}` is unreachable", s)),
+            );
+            defects.extend(
+                self.dead_ends
+                    .iter()
+                    .map(|s| format!("State `{}` has no outgoing edges", s)),
+            );
+            defects.extend(
+                self.unmatched_pushdowns
+                    .iter()
+                    .map(|e| format!("Pushdown `{}` has no matching pullup", e)),
+            );
+            defects.extend(
+                self.terminal_exits
+                    .iter()
+                    .map(|e| format!("Terminal state `{}` has outgoing edge `{}`", e.from, e)),
+            );
+            defects
+        }
+    }
+}
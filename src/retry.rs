@@ -0,0 +1,245 @@
+//! Retry policies for the work of [`Action`](::state::Action) states.
+//!
+//! When a run of the work of an action fails, the action is re-entered with its original
+//! Transaction and the work is run again, according to the [`RetryPolicy`] of that action.
+//! The error is only surfaced once all attempts are exhausted.
+//!
+//! Waiting in between attempts is done through a [`Clock`] service, so tests can use a
+//! [`ManualClock`](::service::ManualClock) which doesn't block.
+//!
+//! ```
+//! extern crate automaton_test;
+//! extern crate failure;
+//!
+//! use std::time::Duration;
+//!
+//! use automaton_test::dynamic::AnyMachine;
+//! use automaton_test::function::error::{ErrorKind, RuntimeConstraintError};
+//! use automaton_test::registry::StateId;
+//! use automaton_test::retry::error::RetryExhaustedError;
+//! use automaton_test::retry::{Backoff, RetryPolicy, Retries};
+//! use automaton_test::service::{Clock, ManualClock};
+//! use failure::Fail;
+//!
+//! let clock = ManualClock::default();
+//! let mut retries = Retries::new(clock.clone());
+//! let policy = RetryPolicy {
+//!     max_attempts: 3,
+//!     backoff: Backoff::Exponential {
+//!         initial: Duration::from_secs(1),
+//!         factor: 2,
+//!         max: Duration::from_secs(60),
+//!     },
+//! };
+//! retries.set(StateId::ActionLoad, policy).unwrap();
+//!
+//! let mut machine: AnyMachine = AnyMachine::default();
+//! machine.execute("transition input".parse().unwrap()).unwrap();
+//! machine.execute(r#"push print "Hello""#.parse().unwrap()).unwrap();
+//! machine.execute("push load".parse().unwrap()).unwrap();
+//!
+//! // The paper jams on the first two loads.
+//! let mut jams = 0;
+//! let loaded = retries.run(&mut machine, |machine| {
+//!     if jams < 2 {
+//!         jams += 1;
+//!         let error = RuntimeConstraintError::from(("loaded paper", "paper jam"));
+//!         return Err(machine.error(ErrorKind::LogicError, error));
+//!     }
+//!     Ok(machine.state())
+//! });
+//! assert_eq!(loaded.unwrap(), StateId::ActionLoad);
+//! // Waited 1s and 2s in between the three attempts.
+//! assert_eq!(clock.now(), Duration::from_secs(3));
+//! // Both retries pulled up and pushed down again.
+//! assert_eq!(machine.journal().events.len(), 7);
+//!
+//! // Work which keeps failing surfaces the errors of all attempts.
+//! let error = retries
+//!     .run(&mut machine, |machine| -> Result<(), _> {
+//!         let error = RuntimeConstraintError::from(("loaded paper", "paper jam"));
+//!         Err(machine.error(ErrorKind::LogicError, error))
+//!     })
+//!     .unwrap_err();
+//! let exhausted = error
+//!     .cause()
+//!     .and_then(|cause| cause.downcast_ref::<RetryExhaustedError>())
+//!     .unwrap();
+//! assert_eq!(exhausted.attempts, 3);
+//! assert_eq!(exhausted.failures.len(), 2);
+//! ```
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use self::error::RetryExhaustedError;
+use command::Command;
+use context::Context;
+use dynamic::AnyMachine;
+use function::error::{MachineError, RuntimeConstraintError};
+use marker::Service;
+use registry::StateId;
+use service::Clock;
+
+pub mod error {
+    //! Types for simplifying error handling syntax.
+
+    use function::error::MachineError;
+    use registry::StateId;
+
+    /// Error thrown when all attempts of running the work of an action failed.
+    #[derive(Debug, Fail)]
+    #[fail(display = "Running `{}` failed after {} attempts", state, attempts)]
+    pub struct RetryExhaustedError {
+        /// The action state whose work failed.
+        pub state: StateId,
+        /// Amount of executed attempts.
+        pub attempts: u32,
+        /// Errors of the attempts before the last one, in order.
+        pub failures: Vec<MachineError>,
+        /// Error of the last attempt.
+        #[cause]
+        pub last: MachineError,
+    }
+}
+
+/// Method of calculating the delay in between attempts.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Backoff {
+    /// Wait the same duration before each retry.
+    Fixed(Duration),
+    /// Multiply the delay by `factor` after each retry, up to `max`.
+    Exponential {
+        /// Delay before the first retry.
+        initial: Duration,
+        /// Multiplier applied to the delay after each retry.
+        factor: u32,
+        /// Upper limit of the delay.
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    /// Delay before the provided retry, counting from 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        match *self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential {
+                initial,
+                factor,
+                max,
+            } => {
+                let mut delay = initial;
+                for _ in 1..retry {
+                    delay = match delay.checked_mul(factor) {
+                        Some(d) if d < max => d,
+                        _ => return max,
+                    };
+                }
+                delay.min(max)
+            }
+        }
+    }
+}
+
+/// Declaration of how to retry the work of an action.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum amount of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay in between attempts.
+    pub backoff: Backoff,
+}
+
+/// Service retrying the work of actions according to their retry policy.
+#[derive(Debug, Clone)]
+pub struct Retries<K>
+where
+    K: Clock,
+{
+    clock: K,
+    policies: HashMap<StateId, RetryPolicy>,
+}
+
+impl<K> Service for Retries<K> where K: Clock {}
+
+impl<K> Retries<K>
+where
+    K: Clock,
+{
+    /// Build a retry service without any policies, waiting through the provided clock.
+    pub fn new(clock: K) -> Self {
+        Retries {
+            clock,
+            policies: HashMap::new(),
+        }
+    }
+
+    /// Set the policy for the provided state, replacing any previous one.
+    ///
+    /// # Errors
+    /// Only [`Action`](::state::Action) states can have a retry policy.
+    pub fn set(
+        &mut self,
+        state: StateId,
+        policy: RetryPolicy,
+    ) -> Result<(), RuntimeConstraintError> {
+        if !state.is_actionable() {
+            return Err(("an Action state", state).into());
+        }
+        self.policies.insert(state, policy);
+        Ok(())
+    }
+
+    /// Remove the policy of the provided state.
+    pub fn clear(&mut self, state: StateId) -> Option<RetryPolicy> {
+        self.policies.remove(&state)
+    }
+
+    /// Run the work of the action the machine resides in, until it succeeds or the
+    /// attempts are exhausted.
+    ///
+    /// Before each retry the action is re-entered, by pulling up and pushing down again
+    /// with the Transaction the action held before the first attempt. Actions without a
+    /// retry policy are run once.
+    ///
+    /// # Errors
+    /// A failure to re-enter the action is returned as is, the machine is left in the
+    /// state it failed in.
+    pub fn run<C, F, R>(&self, machine: &mut AnyMachine<C>, mut work: F) -> Result<R, MachineError>
+    where
+        C: Context,
+        F: FnMut(&AnyMachine<C>) -> Result<R, MachineError>,
+    {
+        let state = machine.state();
+        let policy = match self.policies.get(&state) {
+            Some(&policy) => policy,
+            None => return work(machine),
+        };
+        let transaction = machine.transaction();
+
+        let mut failures = vec![];
+        loop {
+            let last = match work(machine) {
+                Ok(result) => return Ok(result),
+                Err(e) => e,
+            };
+            let attempts = failures.len() as u32 + 1;
+            if attempts >= policy.max_attempts {
+                let kind = last.kind();
+                let error = RetryExhaustedError {
+                    state,
+                    attempts,
+                    failures,
+                    last,
+                };
+                return Err(machine.error(kind, error));
+            }
+            failures.push(last);
+
+            self.clock.sleep(policy.backoff.delay(attempts));
+            machine.execute(Command::Pullup(None))?;
+            machine.execute(Command::Pushdown(state, transaction.clone()))?;
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//! State machines defined at runtime, by a configuration file.
+//!
+//! The typestate [`Machine`](::Machine) requires a rebuild for each change of the flow.
+//! A [`Definition`] describes states, their [`Group`], the fields of their Transaction
+//! and the edges between them in TOML or JSON, and a [`DynMachine`] runs it. Pushdown
+//! and pullup are backed by [`StackStorage`], failures are reported as [`MachineError`].
+//!
+//! ```
+//! use automaton_test::registry::EdgeKind;
+//! use automaton_test::runtime::{Definition, DynMachine, DynTransaction};
+//!
+//! let definition = Definition::from_toml(r#"
+//!     initial = "Start"
+//!
+//!     [[states]]
+//!     name = "Start"
+//!     group = "wait"
+//!
+//!     [[states]]
+//!     name = "Input"
+//!     group = "wait"
+//!
+//!     [[states]]
+//!     name = "Print"
+//!     group = "action"
+//!     fields = ["text"]
+//!
+//!     [[states]]
+//!     name = "Finished"
+//!     terminal = true
+//!
+//!     [[edges]]
+//!     kind = "transition"
+//!     from = "Start"
+//!     to = "Input"
+//!
+//!     [[edges]]
+//!     kind = "pushdown"
+//!     from = "Input"
+//!     to = "Print"
+//!
+//!     [[edges]]
+//!     kind = "pullup"
+//!     from = "Print"
+//!     to = "Input"
+//!
+//!     [[edges]]
+//!     kind = "transition"
+//!     from = "Input"
+//!     to = "Finished"
+//! "#).unwrap();
+//!
+//! let mut machine = DynMachine::new(definition.into()).unwrap();
+//! machine.apply(EdgeKind::Transition, "Input", Some(DynTransaction::default())).unwrap();
+//! let print = DynTransaction::default().with("text", "Hello");
+//! machine.apply(EdgeKind::Pushdown, "Print", Some(print)).unwrap();
+//! assert_eq!(machine.state(), "Print");
+//! assert_eq!(machine.transaction().get("text"), Some("Hello"));
+//!
+//! // The Transaction of Print requires a text.
+//! machine.apply(EdgeKind::Pullup, "Input", None).unwrap();
+//! let missing = DynTransaction::default();
+//! assert!(machine.apply(EdgeKind::Pushdown, "Print", Some(missing)).is_err());
+//! assert_eq!(machine.state(), "Input");
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
+use std::mem;
+use std::sync::Arc;
+
+use serde_json;
+use toml;
+
+use self::error::DefinitionError;
+use function::error::{ErrorKind, MachineError, RuntimeConstraintError};
+use function::{State, StateContainer};
+use marker::TransactionContainer;
+use registry::EdgeKind;
+use service::error::StackPopError;
+use service::StackStorage;
+
+pub mod error {
+    //! Types for simplifying error handling syntax.
+
+    use serde_json;
+    use toml;
+
+    use function::error::RuntimeConstraintError;
+
+    /// Error thrown when a [`Definition`](super::Definition) can not be loaded.
+    #[derive(Debug, Fail)]
+    pub enum DefinitionError {
+        /// The TOML document could not be parsed.
+        #[fail(display = "Parsing the TOML definition failed: {}", _0)]
+        Toml(#[cause] toml::de::Error),
+        /// The JSON document could not be parsed.
+        #[fail(display = "Parsing the JSON definition failed: {}", _0)]
+        Json(#[cause] serde_json::Error),
+        /// The parsed definition doesn't describe a valid state machine.
+        #[fail(display = "Invalid definition: {}", _0)]
+        Invalid(#[cause] RuntimeConstraintError),
+    }
+}
+
+/// Grouping of states, matching the nested states within [`state`](::state).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Group {
+    /// See [`Wait`](::state::Wait).
+    Wait,
+    /// See [`Action`](::state::Action).
+    Action,
+}
+
+/// Declaration of one state.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StateDefinition {
+    /// Unique name of the state.
+    pub name: String,
+    /// Group the state is nested in, top-level states have none.
+    #[serde(default)]
+    pub group: Option<Group>,
+    /// Names of the fields the Transaction of this state requires.
+    ///
+    /// A state without fields is entered with the equivalent of
+    /// [`Epsilon`](::transaction::Epsilon).
+    #[serde(default)]
+    pub fields: Vec<String>,
+    /// Terminal states can not have outgoing edges, see
+    /// [`NonTerminalState`](::marker::NonTerminalState).
+    #[serde(default)]
+    pub terminal: bool,
+}
+
+/// Declaration of one edge between two states.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EdgeDefinition {
+    /// The kind of transition.
+    pub kind: EdgeKind,
+    /// Name of the state the machine must reside in before transitioning.
+    pub from: String,
+    /// Name of the state the machine resides in after transitioning.
+    pub to: String,
+}
+
+/// Description of a complete state machine.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Definition {
+    /// Name of the state each machine starts from.
+    pub initial: String,
+    /// All states of the machine.
+    pub states: Vec<StateDefinition>,
+    /// All edges of the machine.
+    #[serde(default)]
+    pub edges: Vec<EdgeDefinition>,
+}
+
+impl Definition {
+    /// Parse and validate a definition from a TOML document.
+    pub fn from_toml(document: &str) -> Result<Self, DefinitionError> {
+        let definition: Definition = toml::from_str(document).map_err(DefinitionError::Toml)?;
+        definition.validate().map_err(DefinitionError::Invalid)?;
+        Ok(definition)
+    }
+
+    /// Parse and validate a definition from a JSON document.
+    pub fn from_json(document: &str) -> Result<Self, DefinitionError> {
+        let definition: Definition =
+            serde_json::from_str(document).map_err(DefinitionError::Json)?;
+        definition.validate().map_err(DefinitionError::Invalid)?;
+        Ok(definition)
+    }
+
+    /// The declaration of the state with the provided name.
+    pub fn state(&self, name: &str) -> Option<&StateDefinition> {
+        self.states.iter().find(|s| s.name == name)
+    }
+
+    /// The declaration of the edge with the provided kind and states.
+    pub fn edge(&self, kind: EdgeKind, from: &str, to: &str) -> Option<&EdgeDefinition> {
+        self.edges
+            .iter()
+            .find(|e| e.kind == kind && e.from == from && e.to == to)
+    }
+
+    /// All edges starting from the state with the provided name.
+    pub fn moves<'a>(&'a self, from: &'a str) -> impl Iterator<Item = &'a EdgeDefinition> + 'a {
+        self.edges.iter().filter(move |e| e.from == from)
+    }
+
+    /// Verify the definition describes a valid state machine.
+    ///
+    /// ```
+    /// use automaton_test::runtime::{Definition, DynTransaction, StateDefinition};
+    ///
+    /// let start = StateDefinition {
+    ///     name: "Start".to_string(),
+    ///     group: None,
+    ///     fields: vec![],
+    ///     terminal: false,
+    /// };
+    /// let mut definition = Definition {
+    ///     initial: "Start".to_string(),
+    ///     states: vec![start],
+    ///     edges: vec![],
+    /// };
+    /// assert!(definition.validate().is_ok());
+    ///
+    /// let print = StateDefinition {
+    ///     name: "Print".to_string(),
+    ///     group: None,
+    ///     fields: vec!["text".to_string(), "text".to_string()],
+    ///     terminal: false,
+    /// };
+    /// let transaction = DynTransaction::default().with("text", "Hello");
+    /// assert!(transaction.check(&print).is_err());
+    /// definition.states.push(print);
+    /// assert!(definition.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), RuntimeConstraintError> {
+        let mut names = BTreeSet::new();
+        for state in &self.states {
+            if !names.insert(state.name.as_str()) {
+                return Err(("unique state names", &state.name).into());
+            }
+            if let Some(field) = duplicate(&state.fields) {
+                let expected = format!("unique field names for {}", state.name);
+                return Err((expected, field).into());
+            }
+        }
+
+        match self.state(&self.initial) {
+            None => return Err(("a declared initial state", &self.initial).into()),
+            Some(s) if !s.fields.is_empty() => {
+                let factual = format!("{} with fields", s.name);
+                return Err(("an initial state without fields", factual).into());
+            }
+            Some(_) => {}
+        }
+
+        for edge in &self.edges {
+            let description = format!("{:?} edge from {} to {}", edge.kind, edge.from, edge.to);
+            let (from, to) = match (self.state(&edge.from), self.state(&edge.to)) {
+                (Some(from), Some(to)) => (from, to),
+                _ => return Err(("edges between declared states", description).into()),
+            };
+            if from.terminal {
+                return Err(("no edges out of terminal states", description).into());
+            }
+            match edge.kind {
+                EdgeKind::Pushdown if to.group != Some(Group::Action) => {
+                    return Err(("pushdown into an action state", description).into())
+                }
+                EdgeKind::Pullup if from.group != Some(Group::Action) => {
+                    return Err(("pullup out of an action state", description).into())
+                }
+                _ => {}
+            }
+            if self.edges.iter().filter(|e| *e == edge).count() > 1 {
+                return Err(("unique edges", description).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The first name which occurs more than once, if any.
+fn duplicate(names: &[String]) -> Option<&str> {
+    let mut seen = BTreeSet::new();
+    names
+        .iter()
+        .map(String::as_str)
+        .find(|name| !seen.insert(*name))
+}
+
+/// Transaction of a runtime-defined state, holding a value for each declared field.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DynTransaction(pub BTreeMap<String, String>);
+
+impl TransactionContainer for DynTransaction {}
+
+impl DynTransaction {
+    /// Set the value of the provided field.
+    pub fn with<K: Into<String>, V: Into<String>>(mut self, field: K, value: V) -> Self {
+        self.0.insert(field.into(), value.into());
+        self
+    }
+
+    /// The value of the provided field.
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.0.get(field).map(String::as_str)
+    }
+
+    /// Verify the Transaction holds exactly the fields declared by the provided state.
+    ///
+    /// The state MUST declare each field once.
+    pub fn check(&self, state: &StateDefinition) -> Result<(), RuntimeConstraintError> {
+        if let Some(field) = duplicate(&state.fields) {
+            let expected = format!("unique field names for {}", state.name);
+            return Err((expected, field).into());
+        }
+        let declared: BTreeSet<&str> = state.fields.iter().map(String::as_str).collect();
+        let provided: BTreeSet<&str> = self.0.keys().map(String::as_str).collect();
+        if declared != provided {
+            let expected = format!("fields {:?} for {}", declared, state.name);
+            return Err((expected, format!("{:?}", provided)).into());
+        }
+        Ok(())
+    }
+}
+
+impl Display for DynTransaction {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+/// State and Transaction stored during pushdown, to be restored on pullup.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Frame {
+    /// Name of the state which pushed down.
+    pub state: String,
+    /// Transaction held by that state.
+    pub transaction: DynTransaction,
+}
+
+impl TransactionContainer for Frame {}
+
+/// State machine running a runtime [`Definition`].
+#[derive(Debug, Clone)]
+pub struct DynMachine {
+    definition: Arc<Definition>,
+    state: String,
+    transaction: DynTransaction,
+    /// Stack storage service to allow Pushdown and Pullup behaviour.
+    pub storage: StackStorage<Frame>,
+}
+
+/// State of each [`DynMachine`], the actual state is only known by name at runtime.
+#[derive(Debug, Clone, Copy)]
+pub struct DynState;
+
+impl State for DynState {
+    type Transaction = DynTransaction;
+}
+
+impl StateContainer for DynMachine {
+    type State = DynState;
+}
+
+impl DynMachine {
+    /// Build a machine residing in the initial state of the provided definition.
+    ///
+    /// # Errors
+    /// The definition is validated first, since its fields are public and it could have
+    /// been built without [`Definition::from_toml`] or [`Definition::from_json`].
+    ///
+    /// ```
+    /// use automaton_test::runtime::{Definition, DynMachine};
+    ///
+    /// let definition = Definition {
+    ///     initial: "Start".to_string(),
+    ///     states: vec![],
+    ///     edges: vec![],
+    /// };
+    /// assert!(DynMachine::new(definition.into()).is_err());
+    /// ```
+    pub fn new(definition: Arc<Definition>) -> Result<Self, DefinitionError> {
+        definition.validate().map_err(DefinitionError::Invalid)?;
+        Ok(DynMachine {
+            state: definition.initial.clone(),
+            transaction: DynTransaction::default(),
+            storage: StackStorage { tape: vec![] },
+            definition,
+        })
+    }
+
+    /// The definition this machine runs.
+    pub fn definition(&self) -> &Definition {
+        &self.definition
+    }
+
+    /// Name of the current state.
+    pub fn state(&self) -> &str {
+        &self.state
+    }
+
+    /// The Transaction held by the current state.
+    pub fn transaction(&self) -> &DynTransaction {
+        &self.transaction
+    }
+
+    /// Edges which can be applied onto the current state.
+    pub fn moves(&self) -> Vec<&EdgeDefinition> {
+        self.definition.moves(&self.state).collect()
+    }
+
+    /// Apply the edge with the provided kind into the provided state.
+    ///
+    /// Transition and pushdown edges require a Transaction for the next state, pullup
+    /// edges restore it from storage and MUST NOT receive one.
+    ///
+    /// # Errors
+    /// The machine is left untouched when the edge is not declared, when the
+    /// Transaction doesn't match the fields of the next state or when the stack
+    /// storage doesn't hold the next state.
+    pub fn apply(
+        &mut self,
+        kind: EdgeKind,
+        to: &str,
+        transaction: Option<DynTransaction>,
+    ) -> Result<(), MachineError> {
+        if self.definition.edge(kind, &self.state, to).is_none() {
+            let expected = format!("a declared {:?} edge from {}", kind, self.state);
+            let error = RuntimeConstraintError::from((expected, to));
+            return Err(MachineError::new(error, ErrorKind::LogicError, self));
+        }
+
+        // All checks run before the machine is changed.
+        match (kind, transaction) {
+            (EdgeKind::Pullup, None) => {
+                match self.storage.tape.last() {
+                    None => {
+                        let error = MachineError::new(StackPopError, ErrorKind::LogicError, self);
+                        return Err(error);
+                    }
+                    Some(frame) if frame.state != to => {
+                        let error = RuntimeConstraintError::from((to, &frame.state));
+                        return Err(MachineError::new(error, ErrorKind::ConstraintError, self));
+                    }
+                    Some(_) => {}
+                }
+                let frame = self.storage.pop().expect("Checked above");
+                self.transaction = frame.transaction;
+            }
+            (EdgeKind::Pullup, Some(t)) => {
+                let error = RuntimeConstraintError::from(("no transaction", t));
+                return Err(MachineError::new(error, ErrorKind::ConstraintError, self));
+            }
+            (_, None) => {
+                let error = RuntimeConstraintError::from(("a transaction", "none"));
+                return Err(MachineError::new(error, ErrorKind::ConstraintError, self));
+            }
+            (kind, Some(t)) => {
+                let state = self.definition.state(to).expect("Validated definition");
+                t.check(state)
+                    .map_err(|e| MachineError::new(e, ErrorKind::ConstraintError, self))?;
+                let transaction = mem::replace(&mut self.transaction, t);
+                if kind == EdgeKind::Pushdown {
+                    let state = self.state.clone();
+                    self.storage
+                        .push(Frame { state, transaction })
+                        .expect("Never type triggered!");
+                }
+            }
+        }
+        self.state = to.to_string();
+        Ok(())
+    }
+}
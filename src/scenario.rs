@@ -0,0 +1,229 @@
+//! Scripted runs of an [`AnyMachine`], with expectations after each step.
+//!
+//! A scenario is plain text, one entry per line. Each step is a command, as described
+//! by the [command language](::command), optionally followed by expectations about
+//! the outcome of that step:
+//!
+//! - `expect state <state>` - the machine resides in the state after the step.
+//! - `expect error <kind>` - the step fails with the [`ErrorKind`].
+//!
+//! Steps which are not expected to fail, MUST succeed. Empty lines and lines starting
+//! with `#` are ignored.
+//!
+//! ```text
+//! transition input
+//! expect state Wait<Input>
+//! push print ""
+//! expect error ConstraintError
+//! push print "Hello"
+//! expect state Action<Print>
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use failure::Fail;
+
+use self::error::DivergenceError;
+use command::{parse_state, Command};
+use context::Context;
+use dynamic::AnyMachine;
+use function::error::{ErrorKind, MachineError, RuntimeConstraintError};
+use registry::StateId;
+
+pub mod error {
+    //! Types for simplifying error handling syntax.
+
+    /// Error thrown when a scenario run doesn't meet its expectations.
+    #[derive(Debug, Fail)]
+    #[fail(display = "Scenario diverged at step {} (line {}): {}", step, line, reason)]
+    pub struct DivergenceError {
+        /// Index of the first step which diverged.
+        pub step: usize,
+        /// Line of the diverging step within the scenario text.
+        pub line: usize,
+        /// Description of the difference between expectation and outcome.
+        pub reason: String,
+    }
+}
+
+/// Expected outcome of a single step.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Expectation {
+    /// The machine resides in the provided state after the step.
+    State(StateId),
+    /// The step fails with the provided kind of error.
+    Error(ErrorKind),
+}
+
+impl Display for Expectation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Expectation::State(state) => write!(f, "expect state {}", state),
+            Expectation::Error(kind) => write!(f, "expect error {:?}", kind),
+        }
+    }
+}
+
+impl FromStr for Expectation {
+    type Err = RuntimeConstraintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["expect", "state", state] => Ok(Expectation::State(parse_state(state)?)),
+            ["expect", "error", "ConstraintError"] => {
+                Ok(Expectation::Error(ErrorKind::ConstraintError))
+            }
+            ["expect", "error", "LogicError"] => Ok(Expectation::Error(ErrorKind::LogicError)),
+            ["expect", "error", "PermissionError"] => {
+                Ok(Expectation::Error(ErrorKind::PermissionError))
+            }
+            _ => {
+                let expected = "expect state <state> or expect error <kind>";
+                Err((expected, s).into())
+            }
+        }
+    }
+}
+
+/// One command together with the expectations of its outcome.
+#[derive(Debug, Clone)]
+pub struct Step {
+    /// Line of the command within the scenario text.
+    pub line: usize,
+    /// The command to execute.
+    pub command: Command,
+    /// Expectations which MUST hold after executing the command.
+    pub expectations: Vec<Expectation>,
+}
+
+/// Sequence of steps to execute against a state machine.
+#[derive(Debug, Clone, Default)]
+pub struct Scenario {
+    /// Steps in order of execution.
+    pub steps: Vec<Step>,
+}
+
+impl FromStr for Scenario {
+    type Err = RuntimeConstraintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut scenario = Scenario::default();
+        for (idx, line) in s.lines().enumerate() {
+            let line_nr = idx + 1;
+            let text = line.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+
+            let with_line = |e: RuntimeConstraintError| {
+                let factual = format!("line {}: {}", line_nr, e);
+                RuntimeConstraintError::from(("valid scenario line", factual))
+            };
+            if text.starts_with("expect ") {
+                let expectation = text.parse().map_err(with_line)?;
+                match scenario.steps.last_mut() {
+                    Some(step) => step.expectations.push(expectation),
+                    None => {
+                        let factual = format!("line {}: expectation before any step", line_nr);
+                        return Err(("a step before the first expectation", factual).into());
+                    }
+                }
+            } else {
+                scenario.steps.push(Step {
+                    line: line_nr,
+                    command: text.parse().map_err(with_line)?,
+                    expectations: vec![],
+                });
+            }
+        }
+        Ok(scenario)
+    }
+}
+
+impl Scenario {
+    /// Execute each step against a new machine, verifying all expectations.
+    ///
+    /// The machine as it's left after the last step is returned.
+    ///
+    /// # Errors
+    /// The first step not meeting its expectations is reported.
+    ///
+    /// ```
+    /// use automaton_test::scenario::Scenario;
+    ///
+    /// let scenario: Scenario = "transition input\npullup\nexpect state start".parse().unwrap();
+    /// let divergence = scenario.run().unwrap_err();
+    /// assert_eq!((divergence.step, divergence.line), (1, 2));
+    /// ```
+    pub fn run(&self) -> Result<AnyMachine, DivergenceError> {
+        let mut machine = AnyMachine::default();
+        self.run_on(&mut machine)?;
+        Ok(machine)
+    }
+
+    /// Execute each step against the provided machine, verifying all expectations.
+    ///
+    /// # Errors
+    /// The first step not meeting its expectations is reported, the machine is left as
+    /// it was after that step.
+    pub fn run_on<C>(&self, machine: &mut AnyMachine<C>) -> Result<(), DivergenceError>
+    where
+        C: Context,
+    {
+        for (idx, step) in self.steps.iter().enumerate() {
+            let diverged = |reason: String| DivergenceError {
+                step: idx,
+                line: step.line,
+                reason,
+            };
+            let expected_error = step
+                .expectations
+                .iter()
+                .filter_map(|e| match *e {
+                    Expectation::Error(kind) => Some(kind),
+                    _ => None,
+                })
+                .next();
+
+            match (machine.execute(step.command.clone()), expected_error) {
+                (Ok(edge), Some(kind)) => {
+                    let reason = format!("expected error {:?}, but `{}` was applied", kind, edge);
+                    return Err(diverged(reason));
+                }
+                (Err(e), None) => {
+                    let reason = format!("`{}` failed: {}", step.command, describe(&e));
+                    return Err(diverged(reason));
+                }
+                (Err(ref e), Some(kind)) if e.kind() != kind => {
+                    let reason = format!("expected error {:?}, found {}", kind, describe(e));
+                    return Err(diverged(reason));
+                }
+                _ => {}
+            }
+
+            for expectation in &step.expectations {
+                if let Expectation::State(state) = *expectation {
+                    if machine.state() != state {
+                        let reason =
+                            format!("expected state `{}`, found `{}`", state, machine.state());
+                        return Err(diverged(reason));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Single line description of the error and its causes.
+fn describe(e: &MachineError) -> String {
+    let mut description = format!("{:?}", e.kind());
+    let mut cause = e.cause();
+    while let Some(c) = cause {
+        description.push_str(&format!(" - {}", c));
+        cause = c.cause();
+    }
+    description
+}
@@ -0,0 +1,146 @@
+//! Transitions scheduled to happen at a later moment in logical time.
+//!
+//! Logical time is counted in ticks, which are advanced by the user through
+//! [`AnyMachine::tick`]. A scheduled command is not checked against the state machine
+//! until it's due, so it could no longer be legal by then.
+//!
+//! ```
+//! use automaton_test::dynamic::AnyMachine;
+//! use automaton_test::registry::StateId;
+//! use automaton_test::schedule::Scheduler;
+//!
+//! let mut scheduler = Scheduler::default();
+//! let mut machine: AnyMachine = AnyMachine::default();
+//! machine.execute("transition input".parse().unwrap()).unwrap();
+//! machine.execute("push print \"Hello\"".parse().unwrap()).unwrap();
+//!
+//! scheduler.schedule_in(3, "push load".parse().unwrap());
+//! assert!(machine.tick(&mut scheduler, 2).is_empty());
+//! let fired = machine.tick(&mut scheduler, 3);
+//! assert!(fired[0].result.is_ok());
+//! assert_eq!(machine.state(), StateId::ActionLoad);
+//! ```
+
+use std::collections::BTreeMap;
+
+use command::Command;
+use context::Context;
+use dynamic::AnyMachine;
+use function::error::MachineError;
+use marker::Service;
+use registry::Edge;
+
+/// Moment in logical time.
+pub type Tick = u64;
+
+/// Identification of a scheduled command, used for cancellation.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct ScheduleId(u64);
+
+/// Service holding commands to be executed at a certain tick.
+///
+/// Commands due at the same tick are executed in the order they were scheduled.
+#[derive(Debug, Clone, Default)]
+pub struct Scheduler {
+    now: Tick,
+    next_id: u64,
+    pending: BTreeMap<(Tick, ScheduleId), Command>,
+}
+
+impl Service for Scheduler {}
+
+impl Scheduler {
+    /// The last tick the scheduler was advanced to.
+    pub fn now(&self) -> Tick {
+        self.now
+    }
+
+    /// Schedule the command to be executed at the provided tick.
+    ///
+    /// Commands scheduled in the past are executed at the next tick.
+    pub fn schedule_at(&mut self, at: Tick, command: Command) -> ScheduleId {
+        let id = ScheduleId(self.next_id);
+        self.next_id += 1;
+        self.pending.insert((at, id), command);
+        id
+    }
+
+    /// Schedule the command to be executed the provided amount of ticks from now.
+    ///
+    /// Delays reaching beyond the last tick schedule the command at the last tick.
+    ///
+    /// ```
+    /// use automaton_test::schedule::Scheduler;
+    ///
+    /// let mut scheduler = Scheduler::default();
+    /// scheduler.advance(10);
+    /// let id = scheduler.schedule_in(u64::max_value(), "pullup".parse().unwrap());
+    /// assert_eq!(scheduler.pending()[0].1, u64::max_value());
+    /// assert!(scheduler.advance(11).is_empty());
+    /// assert_eq!(scheduler.advance(u64::max_value())[0].0, id);
+    /// ```
+    pub fn schedule_in(&mut self, delay: Tick, command: Command) -> ScheduleId {
+        let at = self.now.saturating_add(delay);
+        self.schedule_at(at, command)
+    }
+
+    /// Remove the scheduled command, if it's still pending.
+    pub fn cancel(&mut self, id: ScheduleId) -> Option<Command> {
+        let key = self.pending.keys().find(|&&(_, k)| k == id).cloned()?;
+        self.pending.remove(&key)
+    }
+
+    /// All pending commands in order of execution.
+    pub fn pending(&self) -> Vec<(ScheduleId, Tick, &Command)> {
+        self.pending
+            .iter()
+            .map(|(&(at, id), command)| (id, at, command))
+            .collect()
+    }
+
+    /// Advance to the provided tick and remove all commands which are due, in order
+    /// of execution.
+    ///
+    /// Time never moves backwards, an earlier tick only collects overdue commands.
+    pub fn advance(&mut self, now: Tick) -> Vec<(ScheduleId, Command)> {
+        self.now = self.now.max(now);
+        let later = match self.now.checked_add(1) {
+            Some(next) => self.pending.split_off(&(next, ScheduleId(0))),
+            // Nothing can be scheduled after the last tick.
+            None => BTreeMap::new(),
+        };
+        let due = ::std::mem::replace(&mut self.pending, later);
+        due.into_iter().map(|((_, id), command)| (id, command)).collect()
+    }
+}
+
+/// Outcome of executing a scheduled command.
+#[derive(Debug)]
+pub struct Fired {
+    /// Identification of the scheduled command.
+    pub id: ScheduleId,
+    /// The executed command.
+    pub command: Command,
+    /// The applied edge, or the reason the command was no longer legal.
+    pub result: Result<Edge, MachineError>,
+}
+
+impl<C> AnyMachine<C>
+where
+    C: Context,
+{
+    /// Advance the scheduler to the provided tick and execute all due commands, in order.
+    ///
+    /// A failing command doesn't prevent the remaining due commands from executing.
+    pub fn tick(&mut self, scheduler: &mut Scheduler, now: Tick) -> Vec<Fired> {
+        scheduler
+            .advance(now)
+            .into_iter()
+            .map(|(id, command)| Fired {
+                id,
+                result: self.execute(command.clone()),
+                command,
+            })
+            .collect()
+    }
+}
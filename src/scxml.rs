@@ -0,0 +1,571 @@
+//! Conversion between W3C SCXML documents and machine definitions.
+//!
+//! SCXML has no notion of pushdown and pullup, nor of the fields of a Transaction. Both
+//! are carried by attributes in the [`NAMESPACE`] of this crate: `stm:kind` on
+//! `<transition>` and `stm:fields` on `<state>`. Transitions without `stm:kind` are
+//! plain transitions.
+//!
+//! The nested [`Wait`](::state::Wait) and [`Action`](::state::Action) states map onto
+//! compound `<state>` elements with the ids `Wait` and `Action`, terminal states map
+//! onto `<final>`. A [`Definition`] only holds one active state at a time, so a
+//! `<parallel>` is flattened into one state per combination of the states within its
+//! regions, see [`import`]. Other compound states are rejected on import.
+//!
+//! ```
+//! use automaton_test::scxml::{export, import, registry_definition};
+//! use automaton_test::TRANSITIONS;
+//!
+//! let document = export(&registry_definition(TRANSITIONS));
+//! assert!(document.contains(r#"<final id="Finished"/>"#));
+//!
+//! let original = registry_definition(TRANSITIONS);
+//! let imported = import(&document).unwrap();
+//! assert_eq!(imported.states, original.states);
+//! // Edges are listed per state, so their order differs.
+//! assert_eq!(imported.edges.len(), original.edges.len());
+//! assert!(original.edges.iter().all(|e| imported.edges.contains(e)));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use roxmltree::{Document, Node};
+
+use self::error::ScxmlError;
+use function::error::RuntimeConstraintError;
+use registry::{Edge, EdgeKind, StateId};
+use runtime::{Definition, EdgeDefinition, Group, StateDefinition};
+
+pub mod error {
+    //! Types for simplifying error handling syntax.
+
+    use roxmltree;
+
+    use function::error::RuntimeConstraintError;
+
+    /// Error thrown when an SCXML document can not be imported.
+    #[derive(Debug, Fail)]
+    pub enum ScxmlError {
+        /// The document is not well-formed XML.
+        #[fail(display = "Parsing the SCXML document failed: {}", _0)]
+        Xml(#[cause] roxmltree::Error),
+        /// The document uses elements which have no equivalent in a definition.
+        #[fail(display = "Unsupported SCXML: {}", _0)]
+        Unsupported(#[cause] RuntimeConstraintError),
+        /// The imported definition doesn't describe a valid state machine.
+        #[fail(display = "Invalid definition: {}", _0)]
+        Invalid(#[cause] RuntimeConstraintError),
+    }
+}
+
+/// Namespace of the SCXML elements.
+pub const SCXML_NAMESPACE: &str = "http://www.w3.org/2005/07/scxml";
+
+/// Namespace of the attributes specific to this crate.
+pub const NAMESPACE: &str = "urn:automaton_test";
+
+/// Describe the provided edges of the registry as a definition.
+///
+/// States are named after their inner state, eg `Print` for `Action<Print>`.
+pub fn registry_definition(edges: &[Edge]) -> Definition {
+    let states = StateId::ALL
+        .iter()
+        .map(|&id| StateDefinition {
+            name: local_name(id).to_string(),
+            group: if id.is_waitable() {
+                Some(Group::Wait)
+            } else if id.is_actionable() {
+                Some(Group::Action)
+            } else {
+                None
+            },
+            fields: id.fields().iter().map(|f| f.to_string()).collect(),
+            terminal: id.is_terminal(),
+        })
+        .collect();
+    let edges = edges
+        .iter()
+        .map(|e| EdgeDefinition {
+            kind: e.kind,
+            from: local_name(e.from).to_string(),
+            to: local_name(e.to).to_string(),
+        })
+        .collect();
+    Definition {
+        initial: local_name(StateId::INITIAL).to_string(),
+        states,
+        edges,
+    }
+}
+
+/// Name of the inner state, used as SCXML id.
+fn local_name(id: StateId) -> &'static str {
+    match id {
+        StateId::WaitStart => "Start",
+        StateId::WaitInput => "Input",
+        StateId::ActionLoad => "Load",
+        StateId::ActionPrint => "Print",
+        StateId::Finished => "Finished",
+    }
+}
+
+/// Write the provided definition as SCXML document.
+pub fn export(definition: &Definition) -> String {
+    // Writing into a String never fails.
+    let mut document = String::new();
+    let _ = writeln!(
+        document,
+        r#"<scxml xmlns="{}" xmlns:stm="{}" version="1.0" initial="{}">"#,
+        SCXML_NAMESPACE,
+        NAMESPACE,
+        escape(&definition.initial)
+    );
+    for &(group, id) in &[
+        (Some(Group::Wait), "Wait"),
+        (Some(Group::Action), "Action"),
+        (None, ""),
+    ] {
+        let members: Vec<&StateDefinition> = definition
+            .states
+            .iter()
+            .filter(|s| s.group == group)
+            .collect();
+        let indent = match group {
+            Some(_) if members.is_empty() => continue,
+            Some(_) => {
+                let _ = writeln!(document, r#"  <state id="{}">"#, id);
+                "    "
+            }
+            None => "  ",
+        };
+        for state in members {
+            write_state(&mut document, definition, state, indent);
+        }
+        if group.is_some() {
+            let _ = writeln!(document, "  </state>");
+        }
+    }
+    let _ = writeln!(document, "</scxml>");
+    document
+}
+
+fn write_state(
+    document: &mut String,
+    definition: &Definition,
+    state: &StateDefinition,
+    indent: &str,
+) {
+    let element = if state.terminal { "final" } else { "state" };
+    let fields = match state.fields.len() {
+        0 => String::new(),
+        _ => format!(r#" stm:fields="{}""#, escape(&state.fields.join(" "))),
+    };
+    let edges: Vec<&EdgeDefinition> = definition.moves(&state.name).collect();
+    if edges.is_empty() {
+        let _ = writeln!(
+            document,
+            r#"{}<{} id="{}"{}/>"#,
+            indent,
+            element,
+            escape(&state.name),
+            fields
+        );
+        return;
+    }
+
+    let _ = writeln!(
+        document,
+        r#"{}<{} id="{}"{}>"#,
+        indent,
+        element,
+        escape(&state.name),
+        fields
+    );
+    for edge in edges {
+        let kind = match edge.kind {
+            EdgeKind::Transition => "",
+            EdgeKind::Pushdown => r#" stm:kind="pushdown""#,
+            EdgeKind::Pullup => r#" stm:kind="pullup""#,
+        };
+        let _ = writeln!(
+            document,
+            r#"{}  <transition target="{}"{}/>"#,
+            indent,
+            escape(&edge.to),
+            kind
+        );
+    }
+    let _ = writeln!(document, "{}</{}>", indent, element);
+}
+
+/// Escape the characters which are not allowed within XML attribute values.
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Read a definition from the provided SCXML document.
+///
+/// Without an `initial` attribute on `<scxml>`, the first state of the document is
+/// the initial state. Events, executable content and elements outside the SCXML
+/// namespace are ignored.
+///
+/// A top-level `<parallel>` holds regions, compound `<state>` elements of atomic states.
+/// Each combination of one state per region becomes a state, named after its members
+/// joined by `|` and holding the fields of each of them. A transition within a region
+/// is declared on each combination holding its source, transitions of the `<parallel>`
+/// itself are declared on every combination. Targeting the `<parallel>` enters the
+/// initial state of each region.
+///
+/// ```
+/// use automaton_test::registry::EdgeKind;
+/// use automaton_test::scxml::import;
+///
+/// let definition = import(r#"
+///     <scxml xmlns="http://www.w3.org/2005/07/scxml" xmlns:ui="urn:ui" initial="Idle">
+///       <state id="Idle">
+///         <transition target="Playing"/>
+///       </state>
+///       <parallel id="Playing">
+///         <state id="Sound">
+///           <state id="Muted"><transition target="Loud"/></state>
+///           <state id="Loud"><transition target="Muted"/></state>
+///         </state>
+///         <state id="Light">
+///           <state id="Dark"><transition target="Bright"/></state>
+///           <state id="Bright"/>
+///         </state>
+///         <transition target="Done"/>
+///       </parallel>
+///       <final id="Done"/>
+///       <ui:state id="Ignored"/>
+///     </scxml>
+/// "#).unwrap();
+///
+/// assert_eq!(definition.states.len(), 6);
+/// assert!(definition.edge(EdgeKind::Transition, "Idle", "Muted|Dark").is_some());
+/// assert!(definition.edge(EdgeKind::Transition, "Muted|Bright", "Loud|Bright").is_some());
+/// assert!(definition.edge(EdgeKind::Transition, "Loud|Bright", "Done").is_some());
+/// ```
+///
+/// # Errors
+/// Nested `<parallel>`, compound states other than the `Wait` and `Action` groups and
+/// transitions with none or multiple targets are not supported. The imported definition
+/// is validated, see [`Definition`].
+pub fn import(document: &str) -> Result<Definition, ScxmlError> {
+    let document = Document::parse(document).map_err(ScxmlError::Xml)?;
+    let root = document.root_element();
+    if name_of(root) != Some("scxml") {
+        let error = ("an scxml root element", root.tag_name().name());
+        return Err(ScxmlError::Unsupported(error.into()));
+    }
+
+    let mut states = vec![];
+    let mut edges = vec![];
+    // States within a parallel, mapped onto the flattened state entered through them.
+    let mut aliases = HashMap::new();
+    for node in elements(root) {
+        match (name_of(node), node.attribute("id")) {
+            (Some("state"), Some(id)) if id == "Wait" || id == "Action" => {
+                let group = if id == "Wait" {
+                    Group::Wait
+                } else {
+                    Group::Action
+                };
+                if elements(node).any(|n| name_of(n) == Some("transition")) {
+                    let error = (
+                        "transitions on atomic states only",
+                        format!("transition on group {}", id),
+                    );
+                    return Err(ScxmlError::Unsupported(error.into()));
+                }
+                for child in elements(node) {
+                    read_state(child, Some(group), &mut states, &mut edges)
+                        .map_err(ScxmlError::Unsupported)?;
+                }
+            }
+            (Some("parallel"), _) => read_parallel(node, &mut states, &mut edges, &mut aliases)
+                .map_err(ScxmlError::Unsupported)?,
+            _ => {
+                read_state(node, None, &mut states, &mut edges).map_err(ScxmlError::Unsupported)?
+            }
+        }
+    }
+    for edge in &mut edges {
+        if let Some(to) = aliases.get(&edge.to) {
+            edge.to = to.clone();
+        }
+    }
+
+    let initial = match root.attribute("initial") {
+        Some(initial) => aliases
+            .get(initial)
+            .cloned()
+            .unwrap_or_else(|| initial.to_string()),
+        None => match states.first() {
+            Some(state) => state.name.clone(),
+            None => return Err(ScxmlError::Invalid(("at least one state", "none").into())),
+        },
+    };
+    let definition = Definition {
+        initial,
+        states,
+        edges,
+    };
+    definition.validate().map_err(ScxmlError::Invalid)?;
+    Ok(definition)
+}
+
+/// Local name of the element, if it's part of the SCXML namespace.
+fn name_of<'a, 'input>(node: Node<'a, 'input>) -> Option<&'input str> {
+    if node.is_element() && node.tag_name().namespace() == Some(SCXML_NAMESPACE) {
+        Some(node.tag_name().name())
+    } else {
+        None
+    }
+}
+
+/// Child elements of the provided node, skipping elements without meaning for the
+/// definition.
+fn elements<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(|n| match name_of(*n) {
+        None | Some("datamodel") | Some("script") | Some("onentry") | Some("onexit")
+        | Some("invoke") | Some("initial") => false,
+        Some(_) => true,
+    })
+}
+
+/// Atomic `<state>` or `<final>` element, with the kind and target of its transitions.
+struct Atomic {
+    state: StateDefinition,
+    transitions: Vec<(EdgeKind, String)>,
+}
+
+/// Region of a `<parallel>` element.
+struct Region {
+    members: Vec<Atomic>,
+    /// Index of the member entered with the parallel element.
+    initial: usize,
+}
+
+/// Read an atomic `<state>` or `<final>` element, with its transitions.
+fn read_state(
+    node: Node,
+    group: Option<Group>,
+    states: &mut Vec<StateDefinition>,
+    edges: &mut Vec<EdgeDefinition>,
+) -> Result<(), RuntimeConstraintError> {
+    let atomic = read_atomic(node)?;
+    for (kind, to) in atomic.transitions {
+        edges.push(EdgeDefinition {
+            kind,
+            from: atomic.state.name.clone(),
+            to,
+        });
+    }
+    states.push(StateDefinition {
+        group,
+        ..atomic.state
+    });
+    Ok(())
+}
+
+fn read_atomic(node: Node) -> Result<Atomic, RuntimeConstraintError> {
+    let element = node.tag_name().name();
+    let terminal = match name_of(node) {
+        Some("state") => false,
+        Some("final") => true,
+        _ => return Err(("a state or final element", element).into()),
+    };
+    let name = node
+        .attribute("id")
+        .ok_or_else(|| RuntimeConstraintError::from(("an id for each state", element)))?;
+    let fields = node
+        .attribute((NAMESPACE, "fields"))
+        .map(|f| f.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+
+    let mut transitions = vec![];
+    for child in elements(node) {
+        if name_of(child) != Some("transition") {
+            let factual = format!("{} within {}", child.tag_name().name(), name);
+            return Err(("an atomic state", factual).into());
+        }
+        transitions.push(read_transition(child, name)?);
+    }
+
+    Ok(Atomic {
+        state: StateDefinition {
+            name: name.to_string(),
+            group: None,
+            fields,
+            terminal,
+        },
+        transitions,
+    })
+}
+
+/// Read the kind and target of a `<transition>` element declared on state `from`.
+fn read_transition(node: Node, from: &str) -> Result<(EdgeKind, String), RuntimeConstraintError> {
+    let target = node.attribute("target").unwrap_or_default();
+    let mut targets = target.split_whitespace();
+    let to = match (targets.next(), targets.next()) {
+        (Some(to), None) => to,
+        _ => {
+            let factual = format!("target {:?} from {}", target, from);
+            return Err(("exactly one transition target", factual).into());
+        }
+    };
+    let kind = match node.attribute((NAMESPACE, "kind")) {
+        None | Some("transition") => EdgeKind::Transition,
+        Some("pushdown") => EdgeKind::Pushdown,
+        Some("pullup") => EdgeKind::Pullup,
+        Some(kind) => return Err(("transition, pushdown or pullup", kind).into()),
+    };
+    Ok((kind, to.to_string()))
+}
+
+/// Read a `<parallel>` element, flattening its regions, see [`import`].
+///
+/// Each state within the parallel element is aliased to the flattened state which is
+/// entered when targeting it from outside.
+fn read_parallel(
+    node: Node,
+    states: &mut Vec<StateDefinition>,
+    edges: &mut Vec<EdgeDefinition>,
+    aliases: &mut HashMap<String, String>,
+) -> Result<(), RuntimeConstraintError> {
+    let id = node.attribute("id").unwrap_or_default();
+    // Transitions of the parallel element are declared on every combination.
+    let mut shared = vec![];
+    let mut regions = vec![];
+    for child in elements(node) {
+        match name_of(child) {
+            Some("transition") => shared.push(read_transition(child, id)?),
+            Some("state") => regions.push(read_region(child)?),
+            _ => {
+                let factual = format!("{} within {}", child.tag_name().name(), id);
+                return Err(("regions within a parallel element", factual).into());
+            }
+        }
+    }
+    if regions.is_empty() {
+        return Err(("regions within a parallel element", id).into());
+    }
+
+    // Every combination of one member per region, by index of the member.
+    let mut combinations: Vec<Vec<usize>> = vec![vec![]];
+    for region in &regions {
+        combinations = combinations
+            .into_iter()
+            .flat_map(|c| {
+                (0..region.members.len()).map(move |i| {
+                    let mut c = c.clone();
+                    c.push(i);
+                    c
+                })
+            })
+            .collect();
+    }
+
+    for combination in &combinations {
+        let from = combined_name(&regions, combination);
+        for (r, region) in regions.iter().enumerate() {
+            for &(kind, ref to) in &region.members[combination[r]].transitions {
+                let to = match region.members.iter().position(|m| m.state.name == *to) {
+                    // Only the member of this region changes.
+                    Some(i) => {
+                        let mut next = combination.clone();
+                        next[r] = i;
+                        combined_name(&regions, &next)
+                    }
+                    None if regions
+                        .iter()
+                        .any(|o| o.members.iter().any(|m| m.state.name == *to)) =>
+                    {
+                        let factual = format!("transition from {} to {}", from, to);
+                        return Err(("transitions within one region", factual).into());
+                    }
+                    None => to.clone(),
+                };
+                edges.push(EdgeDefinition {
+                    kind,
+                    from: from.clone(),
+                    to,
+                });
+            }
+        }
+        for &(kind, ref to) in &shared {
+            edges.push(EdgeDefinition {
+                kind,
+                from: from.clone(),
+                to: to.clone(),
+            });
+        }
+
+        let members: Vec<&Atomic> = regions
+            .iter()
+            .zip(combination)
+            .map(|(region, &i)| &region.members[i])
+            .collect();
+        states.push(StateDefinition {
+            name: from,
+            group: None,
+            fields: members
+                .iter()
+                .flat_map(|m| m.state.fields.iter().cloned())
+                .collect(),
+            terminal: shared.is_empty() && members.iter().all(|m| m.state.terminal),
+        });
+    }
+
+    let initial: Vec<usize> = regions.iter().map(|r| r.initial).collect();
+    if !id.is_empty() {
+        aliases.insert(id.to_string(), combined_name(&regions, &initial));
+    }
+    for (r, region) in regions.iter().enumerate() {
+        for (i, member) in region.members.iter().enumerate() {
+            let mut entered = initial.clone();
+            entered[r] = i;
+            aliases.insert(member.state.name.clone(), combined_name(&regions, &entered));
+        }
+    }
+    Ok(())
+}
+
+/// Read a region of a `<parallel>` element, a compound `<state>` of atomic states.
+fn read_region(node: Node) -> Result<Region, RuntimeConstraintError> {
+    let id = node.attribute("id").unwrap_or_default();
+    let mut members = vec![];
+    for child in elements(node) {
+        if name_of(child) == Some("transition") {
+            let factual = format!("transition on region {}", id);
+            return Err(("transitions on atomic states only", factual).into());
+        }
+        members.push(read_atomic(child)?);
+    }
+    let initial = match node.attribute("initial") {
+        Some(initial) => members
+            .iter()
+            .position(|m| m.state.name == initial)
+            .ok_or_else(|| RuntimeConstraintError::from(("a state of the region", initial)))?,
+        None => 0,
+    };
+    if members.is_empty() {
+        return Err(("states within each region", id).into());
+    }
+    Ok(Region { members, initial })
+}
+
+/// Name of the flattened state holding the provided member of each region.
+fn combined_name(regions: &[Region], combination: &[usize]) -> String {
+    regions
+        .iter()
+        .zip(combination)
+        .map(|(region, &i)| region.members[i].state.name.as_str())
+        .collect::<Vec<_>>()
+        .join("|")
+}
@@ -0,0 +1,264 @@
+//! Supervision of a running state machine, recovering from failures.
+//!
+//! The [`Supervisor`] executes commands onto a machine and keeps a checkpoint of it,
+//! refreshed periodically. When a command fails, the configured [`Strategy`] decides
+//! whether the machine restarts from the machine the supervisor was built with, is
+//! restored from the last checkpoint or whether the error escalates to the caller.
+//! Each failure is recorded as an [`Incident`].
+//!
+//! The snapshot within a [`MachineError`] only allows inspection, so checkpoints are
+//! full copies of the machine.
+//!
+//! ```
+//! use automaton_test::dynamic::AnyMachine;
+//! use automaton_test::registry::StateId;
+//! use automaton_test::supervisor::{Recovery, Strategy, Supervised, Supervisor, SupervisorPolicy};
+//!
+//! let mut machine: AnyMachine = AnyMachine::default();
+//! let policy = SupervisorPolicy {
+//!     strategy: Strategy::RestoreCheckpoint,
+//!     max_restarts: 1,
+//!     checkpoint_interval: 1,
+//! };
+//! let mut supervisor = Supervisor::new(policy, &machine);
+//! supervisor.execute(&mut machine, "transition input".parse().unwrap()).unwrap();
+//! supervisor.execute(&mut machine, "push print \"Hello\"".parse().unwrap()).unwrap();
+//!
+//! // Pulling up into a state which didn't push is a logic error.
+//! let outcome = supervisor.execute(&mut machine, "pullup start".parse().unwrap()).unwrap();
+//! assert_eq!(outcome, Supervised::Recovered(Recovery::Restored));
+//! assert_eq!(machine.state(), StateId::ActionPrint);
+//!
+//! // The restart limit is reached, so the next failure escalates.
+//! assert!(supervisor.execute(&mut machine, "finish".parse().unwrap()).is_err());
+//! assert_eq!(supervisor.audit().len(), 2);
+//! ```
+
+use self::error::EscalationError;
+use command::Command;
+use context::Context;
+use dynamic::AnyMachine;
+use function::error::{ErrorKind, MachineError};
+use marker::Service;
+use registry::{Edge, StateId};
+
+pub mod error {
+    //! Types for simplifying error handling syntax.
+
+    use function::error::MachineError;
+
+    /// Error thrown when the supervisor could not recover from a failure.
+    #[derive(Debug, Fail)]
+    #[fail(display = "Supervisor escalated after {} restarts", restarts)]
+    pub struct EscalationError {
+        /// Amount of recoveries performed before escalating.
+        pub restarts: u32,
+        /// The failure which escalated.
+        #[cause]
+        pub inner: MachineError,
+    }
+}
+
+/// Method of recovering from a failing command.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Strategy {
+    /// Replace the machine with the one the supervisor was built with.
+    ///
+    /// Its services, middleware and context are restored along with its state.
+    Restart,
+    /// Replace the machine with the last checkpoint.
+    RestoreCheckpoint,
+    /// Return the error to the caller without recovering.
+    Escalate,
+}
+
+/// Declaration of how to supervise a machine.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SupervisorPolicy {
+    /// Recovery applied to failures.
+    pub strategy: Strategy,
+    /// Maximum amount of recoveries, after which failures escalate.
+    pub max_restarts: u32,
+    /// Amount of successfully executed commands in between checkpoints.
+    ///
+    /// Zero disables automatic checkpoints, see [`Supervisor::checkpoint`].
+    pub checkpoint_interval: usize,
+}
+
+/// Recovery applied to a failure.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Recovery {
+    /// The machine was replaced with the one the supervisor was built with.
+    Restarted,
+    /// The machine was replaced with the last checkpoint.
+    Restored,
+    /// The failure was returned to the caller.
+    Escalated,
+}
+
+/// Record of a failure handled by the supervisor.
+#[derive(Debug, Clone)]
+pub struct Incident {
+    /// The command which failed.
+    pub command: Command,
+    /// State of the machine when the command failed.
+    pub state: StateId,
+    /// Category of the failure.
+    pub kind: ErrorKind,
+    /// Textual representation of the failure.
+    pub reason: String,
+    /// Recovery applied to the failure.
+    pub recovery: Recovery,
+}
+
+/// Result of executing a command under supervision.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Supervised {
+    /// The command was applied through the provided edge.
+    Applied(Edge),
+    /// The command failed and the machine was recovered.
+    Recovered(Recovery),
+}
+
+/// Service executing commands and recovering the machine from failures.
+#[derive(Debug, Clone)]
+pub struct Supervisor<C = ()>
+where
+    C: Context,
+{
+    policy: SupervisorPolicy,
+    /// The machine the supervisor was built with, restored by [`Strategy::Restart`].
+    initial: AnyMachine<C>,
+    checkpoint: AnyMachine<C>,
+    /// Amount of commands executed since the last checkpoint.
+    since_checkpoint: usize,
+    restarts: u32,
+    audit: Vec<Incident>,
+}
+
+impl<C> Service for Supervisor<C> where C: Context {}
+
+impl<C> Supervisor<C>
+where
+    C: Context,
+{
+    /// Build a supervisor, using the provided machine as first checkpoint and as the
+    /// machine to restart from.
+    ///
+    /// ```
+    /// use automaton_test::dynamic::AnyMachine;
+    /// use automaton_test::registry::StateId;
+    /// use automaton_test::supervisor::{
+    ///     Recovery, Strategy, Supervised, Supervisor, SupervisorPolicy,
+    /// };
+    ///
+    /// let mut machine: AnyMachine = AnyMachine::default();
+    /// machine.execute("transition input".parse().unwrap()).unwrap();
+    /// let policy = SupervisorPolicy {
+    ///     strategy: Strategy::Restart,
+    ///     max_restarts: 1,
+    ///     checkpoint_interval: 0,
+    /// };
+    /// let mut supervisor = Supervisor::new(policy, &machine);
+    /// supervisor.execute(&mut machine, "push print \"Hello\"".parse().unwrap()).unwrap();
+    ///
+    /// let outcome = supervisor.execute(&mut machine, "pullup start".parse().unwrap()).unwrap();
+    /// assert_eq!(outcome, Supervised::Recovered(Recovery::Restarted));
+    /// assert_eq!(machine.state(), StateId::WaitInput);
+    /// assert_eq!(machine.journal().events.len(), 1);
+    /// ```
+    pub fn new(policy: SupervisorPolicy, machine: &AnyMachine<C>) -> Self {
+        Supervisor {
+            policy,
+            initial: machine.clone(),
+            checkpoint: machine.clone(),
+            since_checkpoint: 0,
+            restarts: 0,
+            audit: vec![],
+        }
+    }
+
+    /// Store a copy of the provided machine as the checkpoint to restore.
+    pub fn checkpoint(&mut self, machine: &AnyMachine<C>) {
+        self.checkpoint = machine.clone();
+        self.since_checkpoint = 0;
+    }
+
+    /// The machine which would be restored by [`Strategy::RestoreCheckpoint`].
+    pub fn last_checkpoint(&self) -> &AnyMachine<C> {
+        &self.checkpoint
+    }
+
+    /// Amount of recoveries performed so far.
+    pub fn restarts(&self) -> u32 {
+        self.restarts
+    }
+
+    /// All handled failures in order of occurrence.
+    pub fn audit(&self) -> &[Incident] {
+        &self.audit
+    }
+
+    /// Execute the command onto the machine, see [`AnyMachine::execute`].
+    ///
+    /// A failure is recovered according to the policy, the machine then holds the
+    /// recovered state.
+    ///
+    /// # Errors
+    /// The failure is returned, wrapped in an [`EscalationError`], when the strategy
+    /// is [`Strategy::Escalate`] or the restart limit was reached. The machine is left
+    /// untouched in that case.
+    pub fn execute(
+        &mut self,
+        machine: &mut AnyMachine<C>,
+        command: Command,
+    ) -> Result<Supervised, MachineError> {
+        let error = match machine.execute(command.clone()) {
+            Ok(edge) => {
+                self.since_checkpoint += 1;
+                if self.policy.checkpoint_interval > 0
+                    && self.since_checkpoint >= self.policy.checkpoint_interval
+                {
+                    self.checkpoint(machine);
+                }
+                return Ok(Supervised::Applied(edge));
+            }
+            Err(e) => e,
+        };
+
+        let recovery = match self.policy.strategy {
+            _ if self.restarts >= self.policy.max_restarts => Recovery::Escalated,
+            Strategy::Restart => Recovery::Restarted,
+            Strategy::RestoreCheckpoint => Recovery::Restored,
+            Strategy::Escalate => Recovery::Escalated,
+        };
+        self.audit.push(Incident {
+            command,
+            state: machine.state(),
+            kind: error.kind(),
+            reason: error.to_string(),
+            recovery,
+        });
+
+        match recovery {
+            Recovery::Restarted => {
+                *machine = self.initial.clone();
+                self.checkpoint(machine);
+            }
+            Recovery::Restored => {
+                *machine = self.checkpoint.clone();
+                self.since_checkpoint = 0;
+            }
+            Recovery::Escalated => {
+                let kind = error.kind();
+                let error = EscalationError {
+                    restarts: self.restarts,
+                    inner: error,
+                };
+                return Err(machine.error(kind, error));
+            }
+        }
+        self.restarts += 1;
+        Ok(Supervised::Recovered(recovery))
+    }
+}
@@ -0,0 +1,129 @@
+//! Deadlines for leaving [`Wait`](::state::Wait) states.
+//!
+//! A Wait state pauses the state machine until some event happens, which could take
+//! forever. A [`Timeout`] describes the command to execute when the machine resides in
+//! a certain Wait state for too long, eg transition into `Finished` or pushdown into
+//! `Action<Print>` with a reminder.
+//!
+//! The time spent within a state is taken from the [`Stopwatch`] service of the machine,
+//! which is driven by a [`Clock`], so tests can use a [`ManualClock`].
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use automaton_test::dynamic::AnyMachine;
+//! use automaton_test::registry::StateId;
+//! use automaton_test::service::ManualClock;
+//! use automaton_test::timeout::{Deadlines, Timeout};
+//! use automaton_test::Machine;
+//!
+//! let clock = ManualClock::default();
+//! let mut deadlines = Deadlines::new();
+//! let timeout = Timeout {
+//!     after: Duration::from_secs(30),
+//!     command: "finish".parse().unwrap(),
+//! };
+//! deadlines.set(StateId::WaitInput, timeout).unwrap();
+//!
+//! let mut machine = AnyMachine::WaitStart(Machine::with_clock(clock.clone()));
+//! clock.advance(Duration::from_secs(10));
+//! machine.execute("transition input".parse().unwrap()).unwrap();
+//!
+//! clock.advance(Duration::from_secs(29));
+//! assert_eq!(deadlines.poll(&mut machine).unwrap(), None);
+//! clock.advance(Duration::from_secs(1));
+//! assert!(deadlines.poll(&mut machine).unwrap().is_some());
+//! assert_eq!(machine.state(), StateId::Finished);
+//! ```
+//!
+//! [`Clock`]: ::service::Clock
+//! [`ManualClock`]: ::service::ManualClock
+//! [`Stopwatch`]: ::service::Stopwatch
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use command::Command;
+use context::Context;
+use dynamic::AnyMachine;
+use function::error::{MachineError, RuntimeConstraintError};
+use registry::{Edge, StateId};
+
+/// Command to execute after residing in a state for a certain duration.
+#[derive(Debug, Clone)]
+pub struct Timeout {
+    /// Maximum duration to reside in the state.
+    pub after: Duration,
+    /// Command to execute when the duration has passed.
+    pub command: Command,
+}
+
+/// Service tracking the deadlines of Wait states.
+///
+/// Deadlines count from the moment the state was entered, as recorded by the
+/// [`Stopwatch`](::service::Stopwatch) of the machine.
+#[derive(Debug, Clone, Default)]
+pub struct Deadlines {
+    timeouts: HashMap<StateId, Timeout>,
+}
+
+impl Deadlines {
+    /// Build a deadline service without any timeouts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the timeout for the provided state, replacing any previous one.
+    ///
+    /// # Errors
+    /// Only [`Wait`](::state::Wait) states can have a timeout.
+    pub fn set(&mut self, state: StateId, timeout: Timeout) -> Result<(), RuntimeConstraintError> {
+        if !state.is_waitable() {
+            return Err(("a Wait state", state).into());
+        }
+        self.timeouts.insert(state, timeout);
+        Ok(())
+    }
+
+    /// Remove the timeout of the provided state.
+    pub fn clear(&mut self, state: StateId) -> Option<Timeout> {
+        self.timeouts.remove(&state)
+    }
+
+    /// Time left before the timeout of the current state expires, if it has any.
+    pub fn remaining<C>(&self, machine: &AnyMachine<C>) -> Option<Duration>
+    where
+        C: Context,
+    {
+        let timeout = self.timeouts.get(&machine.state())?;
+        let elapsed = machine.stopwatch().elapsed();
+        Some(timeout.after.checked_sub(elapsed).unwrap_or_default())
+    }
+
+    /// Execute the timeout command of the current state when it has expired.
+    ///
+    /// The applied edge is returned when the timeout fired.
+    ///
+    /// # Errors
+    /// The error of executing the timeout command is returned. The stopwatch of the
+    /// current state is restarted, so the command is not retried immediately.
+    pub fn poll<C>(&self, machine: &mut AnyMachine<C>) -> Result<Option<Edge>, MachineError>
+    where
+        C: Context,
+    {
+        let command = match self.timeouts.get(&machine.state()) {
+            Some(timeout) if machine.stopwatch().elapsed() >= timeout.after => {
+                timeout.command.clone()
+            }
+            _ => return Ok(None),
+        };
+
+        match machine.execute(command) {
+            Ok(edge) => Ok(Some(edge)),
+            Err(e) => {
+                machine.stopwatch_mut().restart();
+                Err(e)
+            }
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! Recording of state machine runs, step by step, and comparison against golden files.
+//!
+//! A trace holds the outcome of each executed command: the applied edge or error, the
+//! state visited, its Transaction and the depth of the stack storage. The textual
+//! representation of a trace is stable, so it can be checked in as a golden file and
+//! compared against later runs.
+//!
+//! The transition implementations record each state they enter into the [`Trace`]
+//! service of the machine, so runs of the typed [`Machine`](::Machine) are traced just
+//! like runs driven through commands.
+//!
+//! ```
+//! use automaton_test::dynamic::AnyMachine;
+//! use automaton_test::state::*;
+//! use automaton_test::stm::*;
+//! use automaton_test::trace::TraceRecorder;
+//! use automaton_test::transaction::{Epsilon, PrintTransaction};
+//! use automaton_test::Machine;
+//!
+//! let machine = Machine::new();
+//! let machine: Machine<Wait<Input>> = machine.transition(Epsilon).unwrap();
+//! let machine: Machine<Action<Print>> = machine.pushdown(PrintTransaction("Hi".into())).unwrap();
+//! let machine: Machine<Wait<Input>> = machine.pullup().unwrap();
+//! let typed = TraceRecorder::from_trace(&machine.trace);
+//!
+//! let mut recorder = TraceRecorder::default();
+//! let mut machine = AnyMachine::default();
+//! for command in &["transition input", r#"push print "Hi""#, "pullup input"] {
+//!     recorder.execute(&mut machine, command.parse().unwrap()).unwrap();
+//! }
+//! assert_eq!(typed.to_string(), recorder.to_string());
+//! ```
+
+use std::env;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+use self::error::GoldenError;
+use command::Command;
+use dynamic::AnyMachine;
+use function::error::{ErrorKind, MachineError};
+use marker::Service;
+use registry::{Edge, EdgeKind, StateId};
+use scenario::Scenario;
+use transaction::TransactionItem;
+
+pub mod error {
+    //! Types for simplifying error handling syntax.
+
+    use std::io;
+
+    /// Error thrown when a trace doesn't match its golden file.
+    #[derive(Debug, Fail)]
+    pub enum GoldenError {
+        /// The golden file could not be read or written.
+        #[fail(display = "Accessing golden file `{}` failed: {}", path, inner)]
+        Io {
+            /// Path of the golden file.
+            path: String,
+            /// Reason of the failure.
+            #[cause]
+            inner: io::Error,
+        },
+        /// The trace differs from the golden file.
+        #[fail(
+            display = "Trace differs from golden file `{}` at line {}:\n  expected: {}\n  factual:  {}",
+            path, line, expected, factual
+        )]
+        Mismatch {
+            /// Path of the golden file.
+            path: String,
+            /// First line which differs.
+            line: usize,
+            /// Content of the golden file at that line.
+            expected: String,
+            /// Content of the trace at that line.
+            factual: String,
+        },
+    }
+}
+
+/// One state entered by a state machine.
+#[derive(Debug, Clone)]
+pub struct Step {
+    /// The applied edge, which leads into the entered state.
+    pub edge: Edge,
+    /// The Transaction held by the entered state.
+    pub transaction: TransactionItem,
+    /// Amount of Transactions held by the stack storage after entering.
+    pub depth: usize,
+}
+
+/// Service recording each state entered by a state machine, see [`TraceRecorder`].
+#[derive(Debug, Clone, Default)]
+pub struct Trace {
+    /// Recorded steps in order of application.
+    pub steps: Vec<Step>,
+}
+
+impl Service for Trace {}
+
+impl Trace {
+    /// Append the entered state to the trace.
+    pub fn record(&mut self, edge: Edge, transaction: TransactionItem, depth: usize) {
+        self.steps.push(Step {
+            edge,
+            transaction,
+            depth,
+        });
+    }
+}
+
+/// Outcome of executing one command.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Outcome {
+    /// The command was applied through the provided edge.
+    Applied(Edge),
+    /// The command failed with the provided kind of error.
+    Failed(ErrorKind),
+}
+
+/// Record of one executed command and the machine after it.
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+    /// The executed command.
+    pub command: Command,
+    /// The result of executing the command.
+    pub outcome: Outcome,
+    /// State of the machine after the command.
+    pub state: StateId,
+    /// Textual representation of the Transaction held after the command.
+    pub transaction: String,
+    /// Amount of Transactions held by the stack storage after the command.
+    pub depth: usize,
+}
+
+impl TraceEntry {
+    /// Record of a command which was applied, resulting in the provided step.
+    fn applied(command: Command, step: &Step) -> Self {
+        TraceEntry {
+            command,
+            outcome: Outcome::Applied(step.edge),
+            state: step.edge.to,
+            transaction: step.transaction.to_string(),
+            depth: step.depth,
+        }
+    }
+}
+
+impl Display for TraceEntry {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.outcome {
+            Outcome::Applied(edge) => write!(f, "{} | applied {}", self.command, edge)?,
+            Outcome::Failed(kind) => write!(f, "{} | failed {:?}", self.command, kind)?,
+        }
+        write!(
+            f,
+            " | state {} | transaction {} | depth {}",
+            self.state, self.transaction, self.depth
+        )
+    }
+}
+
+/// Recorder of each command executed onto a state machine.
+#[derive(Debug, Clone, Default)]
+pub struct TraceRecorder {
+    /// Recorded entries in order of execution.
+    pub entries: Vec<TraceEntry>,
+}
+
+impl TraceRecorder {
+    /// Build the trace of a run which was not driven through commands, eg a run of the
+    /// typed [`Machine`](::Machine).
+    ///
+    /// Each step is recorded as the command which applies its edge.
+    pub fn from_trace(trace: &Trace) -> Self {
+        let entries = trace
+            .steps
+            .iter()
+            .map(|step| {
+                let command = match step.edge.kind {
+                    EdgeKind::Transition => {
+                        Command::Transition(step.edge.to, step.transaction.clone())
+                    }
+                    EdgeKind::Pushdown => Command::Pushdown(step.edge.to, step.transaction.clone()),
+                    EdgeKind::Pullup => Command::Pullup(Some(step.edge.to)),
+                };
+                TraceEntry::applied(command, step)
+            })
+            .collect();
+        TraceRecorder { entries }
+    }
+
+    /// Execute the command onto the machine, see [`AnyMachine::execute`], and record
+    /// the outcome.
+    pub fn execute(
+        &mut self,
+        machine: &mut AnyMachine,
+        command: Command,
+    ) -> Result<Edge, MachineError> {
+        let result = machine.execute(command.clone());
+        let entry = match result {
+            Ok(_) => {
+                let step = machine
+                    .trace()
+                    .steps
+                    .last()
+                    .expect("Applied edges are traced");
+                TraceEntry::applied(command, step)
+            }
+            Err(ref e) => TraceEntry {
+                command,
+                outcome: Outcome::Failed(e.kind()),
+                state: machine.state(),
+                transaction: machine.transaction().to_string(),
+                depth: machine.storage().tape.len(),
+            },
+        };
+        self.entries.push(entry);
+        result
+    }
+
+    /// Execute every step of the scenario onto a new machine and record the outcomes.
+    ///
+    /// Expectations of the scenario are ignored, failing steps are part of the trace.
+    pub fn record(scenario: &Scenario) -> Self {
+        let mut recorder = TraceRecorder::default();
+        let mut machine = AnyMachine::default();
+        for step in &scenario.steps {
+            // Failures are recorded as part of the trace.
+            let _ = recorder.execute(&mut machine, step.command.clone());
+        }
+        recorder
+    }
+}
+
+impl Display for TraceRecorder {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+/// Method of handling golden files.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum GoldenMode {
+    /// Compare the trace against the golden file.
+    Verify,
+    /// Overwrite the golden file with the trace.
+    Regenerate,
+}
+
+impl GoldenMode {
+    /// Name of the environment variable which selects [`GoldenMode::Regenerate`] when set.
+    pub const ENV_VAR: &'static str = "REGENERATE_GOLDENS";
+
+    /// Select the mode from the environment, see [`GoldenMode::ENV_VAR`].
+    pub fn from_env() -> Self {
+        match env::var_os(Self::ENV_VAR) {
+            Some(_) => GoldenMode::Regenerate,
+            None => GoldenMode::Verify,
+        }
+    }
+}
+
+/// Compare the trace against, or regenerate, the golden file at the provided path.
+///
+/// # Errors
+/// The first line which differs between the trace and golden file is reported.
+pub fn check_golden<P: AsRef<Path>>(
+    path: P,
+    trace: &TraceRecorder,
+    mode: GoldenMode,
+) -> Result<(), GoldenError> {
+    let path = path.as_ref();
+    let io_error = |inner| GoldenError::Io {
+        path: path.display().to_string(),
+        inner,
+    };
+    let factual = trace.to_string();
+
+    if mode == GoldenMode::Regenerate {
+        return fs::write(path, factual).map_err(io_error);
+    }
+
+    let expected = fs::read_to_string(path).map_err(io_error)?;
+    let mut expected_lines = expected.lines();
+    let mut factual_lines = factual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), factual_lines.next()) {
+            (None, None) => return Ok(()),
+            (e, f) if e == f => line += 1,
+            (e, f) => {
+                return Err(GoldenError::Mismatch {
+                    path: path.display().to_string(),
+                    line,
+                    expected: e.unwrap_or("<end of file>").to_string(),
+                    factual: f.unwrap_or("<end of trace>").to_string(),
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Feeds deliberately broken graphs into the analysis, one per class of defect.
+
+extern crate automaton_test;
+
+use automaton_test::registry::analysis::Analysis;
+use automaton_test::registry::{Edge, EdgeKind, StateId};
+
+fn edge(kind: EdgeKind, from: StateId, to: StateId) -> Edge {
+    Edge { kind, from, to }
+}
+
+/// The declared graph, see [`automaton_test::TRANSITIONS`].
+fn sound() -> Vec<Edge> {
+    vec![
+        edge(EdgeKind::Transition, StateId::WaitStart, StateId::WaitInput),
+        edge(EdgeKind::Transition, StateId::WaitInput, StateId::Finished),
+        edge(EdgeKind::Pushdown, StateId::WaitInput, StateId::ActionPrint),
+        edge(EdgeKind::Pullup, StateId::ActionPrint, StateId::WaitInput),
+        edge(
+            EdgeKind::Pushdown,
+            StateId::ActionPrint,
+            StateId::ActionLoad,
+        ),
+        edge(EdgeKind::Pullup, StateId::ActionLoad, StateId::ActionPrint),
+    ]
+}
+
+#[test]
+fn sound_graph_has_no_defects() {
+    let analysis = Analysis::of(&sound());
+    assert!(analysis.is_sound(), "{:?}", analysis.defects());
+}
+
+#[test]
+fn unreachable_states_are_reported() {
+    // Without the pushdown into `Action<Print>`, neither action can be entered.
+    let edges: Vec<Edge> = sound()
+        .into_iter()
+        .filter(|e| *e != edge(EdgeKind::Pushdown, StateId::WaitInput, StateId::ActionPrint))
+        .collect();
+    let analysis = Analysis::of(&edges);
+
+    assert_eq!(
+        analysis.unreachable(),
+        &[StateId::ActionLoad, StateId::ActionPrint]
+    );
+    assert!(analysis.dead_ends().is_empty());
+    assert!(!analysis.is_sound());
+    assert!(analysis
+        .defects()
+        .contains(&"State `Action<Print>` is unreachable".to_string()));
+}
+
+#[test]
+fn dead_ends_are_reported() {
+    // Without its pullup, `Action<Load>` can be entered but never left.
+    let pullup = edge(EdgeKind::Pullup, StateId::ActionLoad, StateId::ActionPrint);
+    let edges: Vec<Edge> = sound().into_iter().filter(|e| *e != pullup).collect();
+    let analysis = Analysis::of(&edges);
+
+    assert_eq!(analysis.dead_ends(), &[StateId::ActionLoad]);
+    assert!(analysis.unreachable().is_empty());
+    assert!(analysis
+        .defects()
+        .contains(&"State `Action<Load>` has no outgoing edges".to_string()));
+}
+
+#[test]
+fn unmatched_pushdowns_are_reported() {
+    // `Action<Load>` is left by a transition instead of the pullup matching its pushdown.
+    let pullup = edge(EdgeKind::Pullup, StateId::ActionLoad, StateId::ActionPrint);
+    let mut edges: Vec<Edge> = sound().into_iter().filter(|e| *e != pullup).collect();
+    edges.push(edge(
+        EdgeKind::Transition,
+        StateId::ActionLoad,
+        StateId::Finished,
+    ));
+    let analysis = Analysis::of(&edges);
+
+    let pushdown = edge(
+        EdgeKind::Pushdown,
+        StateId::ActionPrint,
+        StateId::ActionLoad,
+    );
+    assert_eq!(analysis.unmatched_pushdowns(), &[pushdown]);
+    assert!(analysis.dead_ends().is_empty());
+    assert_eq!(analysis.defects().len(), 1);
+}
+
+#[test]
+fn terminal_exits_are_reported() {
+    let exit = edge(EdgeKind::Transition, StateId::Finished, StateId::WaitInput);
+    let mut edges = sound();
+    edges.push(exit);
+    let analysis = Analysis::of(&edges);
+
+    assert_eq!(analysis.terminal_exits(), &[exit]);
+    assert_eq!(
+        analysis.defects(),
+        vec!["Terminal state `Finished` has outgoing edge `Finished -> Wait<Input>`".to_string()]
+    );
+}
@@ -0,0 +1,62 @@
+//! Compiles and runs the code generated from `codegen/flow.toml`, which is checked in as
+//! golden file `codegen/flow.rs`.
+//!
+//! Set the `REGENERATE_GOLDENS` environment variable to overwrite the golden file
+//! instead.
+
+extern crate automaton_test;
+
+use std::fs;
+
+use automaton_test::codegen::generate;
+use automaton_test::registry::analysis::Analysis;
+use automaton_test::runtime::Definition;
+use automaton_test::state::{Action, Wait};
+use automaton_test::stm::*;
+use automaton_test::trace::GoldenMode;
+use automaton_test::transaction::Epsilon;
+
+use flow::*;
+
+mod flow {
+    include!("codegen/flow.rs");
+}
+
+#[test]
+fn generated_code_matches_golden() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/codegen");
+    let spec = fs::read_to_string(format!("{}/flow.toml", dir)).expect("Reading spec failed");
+    let definition = Definition::from_toml(&spec).expect("Parsing spec failed");
+    let code = generate(&definition).expect("Generating code failed");
+
+    let golden = format!("{}/flow.rs", dir);
+    if GoldenMode::from_env() == GoldenMode::Regenerate {
+        fs::write(&golden, code).expect("Writing golden file failed");
+        return;
+    }
+    let expected = fs::read_to_string(&golden).expect("Reading golden file failed");
+    assert!(code == expected, "Generated code differs from `{}`", golden);
+}
+
+#[test]
+fn generated_machine_runs() {
+    let machine = Machine::new();
+    let machine: Machine<Wait<Input>> = machine.transition(Epsilon).unwrap();
+    let print = PrintTransaction {
+        text: "Hello".into(),
+        color: "red".into(),
+    };
+    let machine: Machine<Action<Print>> = machine.pushdown(print).unwrap();
+    let machine: Machine<Action<Load>> = machine.pushdown(Epsilon).unwrap();
+    let machine: Machine<Action<Print>> = machine.pullup().unwrap();
+    assert_eq!(machine.transaction.text, "Hello");
+    let machine: Machine<Wait<Input>> = machine.pullup().unwrap();
+    let _: Machine<Finished> = machine.transition(Epsilon).unwrap();
+}
+
+#[test]
+fn generated_registry_is_sound() {
+    let analysis = Analysis::of(TRANSITIONS);
+    assert!(analysis.is_sound(), "{:?}", analysis.defects());
+    assert_eq!(TRANSITIONS[2].to_string(), "Wait<Input> => Action<Print>");
+}
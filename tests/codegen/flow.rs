@@ -0,0 +1,329 @@
+// Generated by `automaton_test::codegen`, DO NOT EDIT!
+
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+
+#[allow(unused_imports)]
+use ::automaton_test::function::error::{ErrorKind, MachineError, RuntimeConstraintError, SnapshottedErrorExt};
+#[allow(unused_imports)]
+use ::automaton_test::function::helper::{pack_transaction, unpack_transaction};
+#[allow(unused_imports)]
+use ::automaton_test::function::{ServiceCompliance, State, StateContainer, Validate};
+#[allow(unused_imports)]
+use ::automaton_test::marker::*;
+#[allow(unused_imports)]
+use ::automaton_test::registry::{Edge, EdgeKind, StateSet};
+#[allow(unused_imports)]
+use ::automaton_test::service::StackStorage;
+#[allow(unused_imports)]
+use ::automaton_test::state::{Action, Wait};
+#[allow(unused_imports)]
+use ::automaton_test::stm::{Checked, PullupFrom, PushdownFrom, TransitionFrom};
+#[allow(unused_imports)]
+use ::automaton_test::transaction::Epsilon;
+
+/// State `Start`.
+#[derive(Debug, Clone)]
+pub struct Start();
+impl State for Start {
+    type Transaction = Epsilon;
+}
+impl WaitableMarker for Start {}
+
+/// State `Input`.
+#[derive(Debug, Clone)]
+pub struct Input();
+impl State for Input {
+    type Transaction = Epsilon;
+}
+impl WaitableMarker for Input {}
+
+/// Transaction to be received by `Print`.
+#[derive(Debug, Clone)]
+pub struct PrintTransaction {
+    #[allow(missing_docs)]
+    pub text: String,
+    #[allow(missing_docs)]
+    pub color: String,
+}
+impl Transaction for PrintTransaction {}
+impl Validate for PrintTransaction {}
+
+/// State `Print`.
+#[derive(Debug, Clone)]
+pub struct Print();
+impl State for Print {
+    type Transaction = PrintTransaction;
+}
+impl ActionableMarker for Print {}
+
+/// State `Load`.
+#[derive(Debug, Clone)]
+pub struct Load();
+impl State for Load {
+    type Transaction = Epsilon;
+}
+impl ActionableMarker for Load {}
+
+/// State `Finished`.
+#[derive(Debug, Clone)]
+pub struct Finished();
+impl State for Finished {
+    type Transaction = Epsilon;
+}
+impl TopLevelMarker for Finished {}
+
+/// Collection of known Transaction structures.
+#[derive(Debug, Clone)]
+pub enum TransactionItem {
+    /// See [`Epsilon`]
+    Epsilon(Epsilon),
+    /// See [`PrintTransaction`]
+    PrintTransaction(PrintTransaction),
+}
+impl TransactionContainer for TransactionItem {}
+
+impl From<Epsilon> for TransactionItem {
+    fn from(x: Epsilon) -> Self {
+        TransactionItem::Epsilon(x)
+    }
+}
+
+impl TryFrom<TransactionItem> for Epsilon {
+    type Error = RuntimeConstraintError;
+
+    fn try_from(tc: TransactionItem) -> Result<Self, Self::Error> {
+        match tc {
+            TransactionItem::Epsilon(x) => Ok(x),
+            e => Err((stringify!(TransactionItem::Epsilon), format!("{:?}", e)).into()),
+        }
+    }
+}
+
+impl From<PrintTransaction> for TransactionItem {
+    fn from(x: PrintTransaction) -> Self {
+        TransactionItem::PrintTransaction(x)
+    }
+}
+
+impl TryFrom<TransactionItem> for PrintTransaction {
+    type Error = RuntimeConstraintError;
+
+    fn try_from(tc: TransactionItem) -> Result<Self, Self::Error> {
+        match tc {
+            TransactionItem::PrintTransaction(x) => Ok(x),
+            e => Err((stringify!(TransactionItem::PrintTransaction), format!("{:?}", e)).into()),
+        }
+    }
+}
+
+/// The state machine.
+#[derive(Debug, Clone)]
+pub struct Machine<X>
+where
+    X: TopLevelMarker + State,
+{
+    /// Field to encode the current state of the machine.
+    pub state: PhantomData<X>,
+    /// Field to store the provided Transaction object as required by the current state.
+    pub transaction: X::Transaction,
+    /// Stack storage service to allow Pushdown and Pullup behaviour.
+    pub storage: StackStorage<TransactionItem>,
+}
+
+impl<X> StateContainer for Machine<X>
+where
+    X: TopLevelMarker + State,
+{
+    type State = X;
+}
+
+impl<X> ServiceCompliance<StackStorage<TransactionItem>> for Machine<X>
+where
+    X: TopLevelMarker + State,
+{
+    fn get(&self) -> &StackStorage<TransactionItem> {
+        &self.storage
+    }
+
+    fn get_mut(&mut self) -> &mut StackStorage<TransactionItem> {
+        &mut self.storage
+    }
+}
+
+impl Machine<Wait<Start>> {
+    /// Build a new state machine residing in the initial state.
+    pub fn new() -> Self {
+        Machine {
+            state: PhantomData,
+            transaction: Epsilon,
+            storage: StackStorage { tape: vec![] },
+        }
+    }
+}
+
+impl Default for Machine<Wait<Start>> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransitionFrom<Machine<Wait<Start>>> for Machine<Wait<Input>> {
+    fn transition_from(
+        old: Machine<Wait<Start>>,
+        t: <Self::State as State>::Transaction,
+        _: Checked,
+    ) -> Self {
+        Machine {
+            state: PhantomData,
+            transaction: t,
+            storage: old.storage,
+        }
+    }
+}
+
+impl TransitionFrom<Machine<Wait<Input>>> for Machine<Finished> {
+    fn transition_from(
+        old: Machine<Wait<Input>>,
+        t: <Self::State as State>::Transaction,
+        _: Checked,
+    ) -> Self {
+        Machine {
+            state: PhantomData,
+            transaction: t,
+            storage: old.storage,
+        }
+    }
+}
+
+impl PushdownFrom<Machine<Wait<Input>>, TransactionItem> for Machine<Action<Print>> {
+    fn pushdown_from(
+        mut old: Machine<Wait<Input>>,
+        t: <Self::State as State>::Transaction,
+        _: Checked,
+    ) -> Self {
+        let old_transaction: TransactionItem = pack_transaction(old.transaction);
+        old.storage.push(old_transaction).expect("Never type triggered!");
+        Machine {
+            state: PhantomData,
+            transaction: t,
+            storage: old.storage,
+        }
+    }
+}
+
+impl PullupFrom<Machine<Action<Print>>, TransactionItem> for Machine<Wait<Input>> {
+    fn pullup_from(mut old: Machine<Action<Print>>, _: Checked) -> Result<Self, MachineError> {
+        let old_transaction = ServiceCompliance::<StackStorage<TransactionItem>>::get_mut(&mut old)
+            .pop()
+            .context(ErrorKind::LogicError, &old)
+            .and_then(|item| unpack_transaction(item).context(ErrorKind::ConstraintError, &old))?;
+        Ok(Machine {
+            state: PhantomData,
+            transaction: old_transaction,
+            storage: old.storage,
+        })
+    }
+}
+
+impl PushdownFrom<Machine<Action<Print>>, TransactionItem> for Machine<Action<Load>> {
+    fn pushdown_from(
+        mut old: Machine<Action<Print>>,
+        t: <Self::State as State>::Transaction,
+        _: Checked,
+    ) -> Self {
+        let old_transaction: TransactionItem = pack_transaction(old.transaction);
+        old.storage.push(old_transaction).expect("Never type triggered!");
+        Machine {
+            state: PhantomData,
+            transaction: t,
+            storage: old.storage,
+        }
+    }
+}
+
+impl PullupFrom<Machine<Action<Load>>, TransactionItem> for Machine<Action<Print>> {
+    fn pullup_from(mut old: Machine<Action<Load>>, _: Checked) -> Result<Self, MachineError> {
+        let old_transaction = ServiceCompliance::<StackStorage<TransactionItem>>::get_mut(&mut old)
+            .pop()
+            .context(ErrorKind::LogicError, &old)
+            .and_then(|item| unpack_transaction(item).context(ErrorKind::ConstraintError, &old))?;
+        Ok(Machine {
+            state: PhantomData,
+            transaction: old_transaction,
+            storage: old.storage,
+        })
+    }
+}
+
+/// Identification of each top-level state.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum StateId {
+    /// See [`Start`].
+    Start,
+    /// See [`Input`].
+    Input,
+    /// See [`Print`].
+    Print,
+    /// See [`Load`].
+    Load,
+    /// See [`Finished`].
+    Finished,
+}
+
+impl StateSet for StateId {
+    const ALL: &'static [StateId] = &[StateId::Start, StateId::Input, StateId::Print, StateId::Load, StateId::Finished];
+
+    const INITIAL: StateId = StateId::Start;
+
+    fn is_terminal(self) -> bool {
+        [StateId::Finished].contains(&self)
+    }
+}
+
+impl ::std::fmt::Display for StateId {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        let name = match *self {
+            StateId::Start => "Wait<Start>",
+            StateId::Input => "Wait<Input>",
+            StateId::Print => "Action<Print>",
+            StateId::Load => "Action<Load>",
+            StateId::Finished => "Finished",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Registry of all implemented edges.
+pub const TRANSITIONS: &[Edge<StateId>] = &[
+    Edge {
+        kind: EdgeKind::Transition,
+        from: StateId::Start,
+        to: StateId::Input,
+    },
+    Edge {
+        kind: EdgeKind::Transition,
+        from: StateId::Input,
+        to: StateId::Finished,
+    },
+    Edge {
+        kind: EdgeKind::Pushdown,
+        from: StateId::Input,
+        to: StateId::Print,
+    },
+    Edge {
+        kind: EdgeKind::Pullup,
+        from: StateId::Print,
+        to: StateId::Input,
+    },
+    Edge {
+        kind: EdgeKind::Pushdown,
+        from: StateId::Print,
+        to: StateId::Load,
+    },
+    Edge {
+        kind: EdgeKind::Pullup,
+        from: StateId::Load,
+        to: StateId::Print,
+    },
+];
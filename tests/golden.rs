@@ -0,0 +1,33 @@
+//! Compares the trace of each scenario within `scenarios/` against its golden file.
+//!
+//! Set the `REGENERATE_GOLDENS` environment variable to overwrite the golden files
+//! instead.
+
+extern crate automaton_test;
+
+use std::ffi::OsStr;
+use std::fs;
+
+use automaton_test::scenario::Scenario;
+use automaton_test::trace::{check_golden, GoldenMode, TraceRecorder};
+
+#[test]
+fn scenario_traces_match_goldens() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/scenarios");
+    let mut checked = 0;
+    for entry in fs::read_dir(dir).expect("Scenario directory missing") {
+        let path = entry.expect("Reading scenario directory failed").path();
+        if path.extension() != Some(OsStr::new("txt")) {
+            continue;
+        }
+
+        let text = fs::read_to_string(&path).expect("Reading scenario failed");
+        let scenario: Scenario = text.parse().expect("Parsing scenario failed");
+        let trace = TraceRecorder::record(&scenario);
+        if let Err(e) = check_golden(path.with_extension("trace"), &trace, GoldenMode::from_env()) {
+            panic!("{}", e);
+        }
+        checked += 1;
+    }
+    assert!(checked > 0, "No scenarios found");
+}
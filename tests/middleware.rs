@@ -0,0 +1,63 @@
+//! Consults the layers of a middleware chain while edges are applied onto the machine
+//! from within those layers.
+
+extern crate automaton_test;
+
+use std::sync::{Arc, Mutex};
+
+use automaton_test::dynamic::AnyMachine;
+use automaton_test::function::error::MachineError;
+use automaton_test::middleware::Step;
+use automaton_test::registry::{Edge, StateId};
+use automaton_test::transaction::Epsilon;
+
+#[test]
+fn layers_stay_installed_while_consulted() {
+    let seen = Arc::new(Mutex::new(vec![]));
+    let mut machine: AnyMachine = AnyMachine::default();
+    let layer_seen = seen.clone();
+    machine
+        .middleware_mut()
+        .push(move |machine: &AnyMachine, _: &Step| {
+            layer_seen.lock().unwrap().push(machine.middleware().len());
+            Ok(())
+        });
+    machine
+        .middleware_mut()
+        .push(|_: &AnyMachine, _: &Step| Ok(()));
+
+    machine
+        .apply(Edge::START_TO_INPUT, Some(Epsilon.into()))
+        .unwrap();
+    assert_eq!(*seen.lock().unwrap(), vec![2]);
+}
+
+#[test]
+fn edges_applied_from_within_a_layer_skip_the_layers() {
+    let calls = Arc::new(Mutex::new(0));
+    let mut machine: AnyMachine = AnyMachine::default();
+    let layer_calls = calls.clone();
+    machine.middleware_mut().push(
+        move |machine: &AnyMachine, _: &Step| -> Result<(), MachineError> {
+            *layer_calls.lock().unwrap() += 1;
+            // The clone shares the chain, which is being consulted by this thread.
+            let mut clone = machine.clone();
+            clone.apply(Edge::START_TO_INPUT, Some(Epsilon.into()))?;
+            assert_eq!(clone.state(), StateId::WaitInput);
+            Ok(())
+        },
+    );
+
+    machine
+        .apply(Edge::START_TO_INPUT, Some(Epsilon.into()))
+        .unwrap();
+    assert_eq!(machine.state(), StateId::WaitInput);
+    assert_eq!(*calls.lock().unwrap(), 1);
+
+    // Clones consult the layers outside of a consultation.
+    let mut clone = machine.clone();
+    assert!(clone
+        .apply(Edge::START_TO_INPUT, Some(Epsilon.into()))
+        .is_err());
+    assert_eq!(*calls.lock().unwrap(), 2);
+}
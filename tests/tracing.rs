@@ -0,0 +1,195 @@
+//! Checks the events and spans emitted through the `tracing` feature.
+#![cfg(feature = "tracing")]
+
+extern crate automaton_test;
+extern crate tracing;
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+use automaton_test::dynamic::AnyMachine;
+use automaton_test::function::error::{ErrorKind, RuntimeConstraintError};
+use automaton_test::registry::Edge;
+use automaton_test::transaction::{Epsilon, PrintTransaction};
+
+/// Subscriber writing a line for each event, opened span and closed span.
+#[derive(Default)]
+struct Recorder {
+    lines: Arc<Mutex<Vec<String>>>,
+    next_id: AtomicU64,
+    /// Amount of handles to each open span.
+    handles: Mutex<HashMap<u64, usize>>,
+}
+
+/// Collects the message and edge of an event.
+#[derive(Default)]
+struct Fields {
+    message: String,
+    edge: String,
+    kind: String,
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        let value = format!("{:?}", value);
+        match field.name() {
+            "message" => self.message = value,
+            "edge" => self.edge = value,
+            "kind" => self.kind = value,
+            _ => {}
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        self.handles.lock().unwrap().insert(id, 1);
+        let parent = span.parent().map(Id::into_u64);
+        let line = format!("open {} {} in {:?}", span.metadata().name(), id, parent);
+        self.lines.lock().unwrap().push(line);
+        Id::from_u64(id)
+    }
+
+    fn record(&self, _: &Id, _: &Record) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let parent = event.parent().map(Id::into_u64);
+        let line = match fields.message.as_str() {
+            "machine error" => format!("error {}", fields.kind),
+            message => format!("{} {} in {:?}", message, fields.edge, parent),
+        };
+        self.lines.lock().unwrap().push(line);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+
+    fn clone_span(&self, id: &Id) -> Id {
+        *self
+            .handles
+            .lock()
+            .unwrap()
+            .get_mut(&id.into_u64())
+            .unwrap() += 1;
+        id.clone()
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        let mut handles = self.handles.lock().unwrap();
+        let count = handles.get_mut(&id.into_u64()).unwrap();
+        *count -= 1;
+        if *count > 0 {
+            return false;
+        }
+        self.lines
+            .lock()
+            .unwrap()
+            .push(format!("close {}", id.into_u64()));
+        true
+    }
+}
+
+#[test]
+fn pushdowns_nest_as_spans() {
+    let recorder = Recorder::default();
+    let lines = recorder.lines.clone();
+    tracing::subscriber::with_default(recorder, || {
+        let mut machine: AnyMachine = AnyMachine::default();
+        let print = PrintTransaction("Hello".into());
+        machine
+            .apply(Edge::START_TO_INPUT, Some(Epsilon.into()))
+            .unwrap();
+        machine
+            .apply(Edge::INPUT_PUSHDOWN_PRINT, Some(print.into()))
+            .unwrap();
+        machine
+            .apply(Edge::PRINT_PUSHDOWN_LOAD, Some(Epsilon.into()))
+            .unwrap();
+        machine.apply(Edge::LOAD_PULLUP_PRINT, None).unwrap();
+        machine.apply(Edge::PRINT_PULLUP_INPUT, None).unwrap();
+        machine
+            .apply(Edge::INPUT_TO_FINISHED, Some(Epsilon.into()))
+            .unwrap();
+        assert!(machine.apply(Edge::START_TO_INPUT, None).is_err());
+    });
+
+    let lines = lines.lock().unwrap();
+    let expected = [
+        "transition Wait<Start> -> Wait<Input> in None",
+        "pushdown Wait<Input> => Action<Print> in None",
+        "open pushdown 1 in None",
+        "pushdown Action<Print> => Action<Load> in Some(1)",
+        "open pushdown 2 in Some(1)",
+        "pullup Action<Print> <= Action<Load> in Some(2)",
+        "close 2",
+        "pullup Wait<Input> <= Action<Print> in Some(1)",
+        "close 1",
+        "transition Wait<Input> -> Finished in None",
+        "error LogicError",
+    ];
+    assert_eq!(lines.as_slice(), &expected[..]);
+}
+
+#[test]
+fn clones_hold_no_spans() {
+    let recorder = Recorder::default();
+    let lines = recorder.lines.clone();
+    tracing::subscriber::with_default(recorder, || {
+        let mut machine: AnyMachine = AnyMachine::default();
+        let print = PrintTransaction("Hello".into());
+        machine
+            .apply(Edge::START_TO_INPUT, Some(Epsilon.into()))
+            .unwrap();
+        machine
+            .apply(Edge::INPUT_PUSHDOWN_PRINT, Some(print.into()))
+            .unwrap();
+
+        // The span closes on pullup, even though the checkpoint is still around.
+        let checkpoint = machine.clone();
+        machine.apply(Edge::PRINT_PULLUP_INPUT, None).unwrap();
+        assert_eq!(lines.lock().unwrap().last().unwrap(), "close 1");
+        drop(checkpoint);
+    });
+}
+
+#[test]
+fn failing_edges_warn_once() {
+    let recorder = Recorder::default();
+    let lines = recorder.lines.clone();
+    tracing::subscriber::with_default(recorder, || {
+        let mut machine: AnyMachine = AnyMachine::default();
+        machine
+            .apply(Edge::START_TO_INPUT, Some(Epsilon.into()))
+            .unwrap();
+        // Building an error doesn't emit anything, only a failing edge does.
+        let error = RuntimeConstraintError::from(("nothing", "something"));
+        let _ = machine.error(ErrorKind::LogicError, error);
+        let empty = PrintTransaction(String::new());
+        assert!(machine
+            .apply(Edge::INPUT_PUSHDOWN_PRINT, Some(empty.into()))
+            .is_err());
+    });
+
+    let lines = lines.lock().unwrap();
+    let expected = [
+        "transition Wait<Start> -> Wait<Input> in None",
+        "error ConstraintError",
+    ];
+    assert_eq!(lines.as_slice(), &expected[..]);
+}